@@ -2,13 +2,65 @@
 // Manages agent discovery, capabilities, and pricing on Solana
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::system_program;
 
 declare_id!("9u4eVWRf8a7vMDCHsguakB6vxcnCuJssBVBbQAYrKdog"); // Deployed Devnet ID
 
+// Agents kept in RegistryConfig.top_agents, highest reputation first
+pub const TOP_AGENTS_LEN: usize = 10;
+// String and vector budgets, applied through the max_len annotations on AgentProfile
+pub const MAX_AGENT_NAME_LEN: usize = 60;
+pub const MAX_ENDPOINT_LEN: usize = 124;
+pub const MAX_CAPABILITIES: usize = 20;
+pub const MAX_CAPABILITY_LEN: usize = 28;
+// Also the size migrate_registry_config grows older configs to
+pub const REGISTRY_CONFIG_SPACE: usize = 8 + RegistryConfig::INIT_SPACE;
+
 #[program]
 pub mod solana_agent_registry {
     use super::*;
 
+    // Create the registry-wide configuration (one per program)
+    pub fn initialize_registry(
+        ctx: Context<InitializeRegistry>,
+        expiry_seconds: i64,
+        dispute_window: i64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.expiry_seconds = expiry_seconds; // 0 disables expiry
+        config.dispute_window = dispute_window;
+        config.reputation_increment = 1;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    // Tune how much reputation a confirmed success is worth (registry admin only)
+    pub fn set_reputation_increment(
+        ctx: Context<UpdateRegistryConfig>,
+        reputation_increment: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.reputation_increment = reputation_increment;
+        Ok(())
+    }
+
+    // Bound how many capability tags an agent may register with (registry admin only)
+    pub fn set_capability_limits(
+        ctx: Context<UpdateRegistryConfig>,
+        min_capabilities: u8,
+        max_capabilities: u8,
+    ) -> Result<()> {
+        require!(
+            max_capabilities == 0 || max_capabilities >= min_capabilities.max(1),
+            AgentError::InvalidCapabilityLimits
+        );
+        let config = &mut ctx.accounts.config;
+        config.min_capabilities = min_capabilities;
+        config.max_capabilities = max_capabilities;
+        Ok(())
+    }
+
     // Register a new agent profile
     pub fn register_agent(
         ctx: Context<RegisterAgent>,
@@ -16,6 +68,15 @@ pub mod solana_agent_registry {
         endpoint: String,
         capabilities: Vec<String>,
     ) -> Result<()> {
+        let capabilities = ctx.accounts.config.validate_capabilities(capabilities)?;
+        validate_endpoint(&endpoint)?;
+
+        // Names are unique; a live record means another agent holds this one
+        let name_record = &mut ctx.accounts.name_record;
+        require!(name_record.agent_profile == Pubkey::default(), AgentError::NameTaken);
+        name_record.agent_profile = ctx.accounts.agent_profile.key();
+        name_record.bump = ctx.bumps.name_record;
+
         let agent_profile = &mut ctx.accounts.agent_profile;
         agent_profile.authority = ctx.accounts.authority.key();
         agent_profile.name = name;
@@ -25,6 +86,182 @@ pub mod solana_agent_registry {
         agent_profile.total_jobs = 0;
         agent_profile.registered_at = Clock::get()?.unix_timestamp;
         agent_profile.is_active = true;
+        agent_profile.last_heartbeat = agent_profile.registered_at;
+        agent_profile.bump = ctx.bumps.agent_profile;
+
+        let config = &mut ctx.accounts.config;
+        config.total_agents += 1;
+        config.active_agents += 1;
+        Ok(())
+    }
+
+    // Pause or resume discovery of the caller's agent
+    pub fn set_agent_active(ctx: Context<SetAgentActive>, is_active: bool) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        if agent_profile.is_active != is_active {
+            let config = &mut ctx.accounts.config;
+            config.active_agents = if is_active {
+                config.active_agents + 1
+            } else {
+                config.active_agents.saturating_sub(1)
+            };
+            agent_profile.is_active = is_active;
+        }
+        Ok(())
+    }
+
+    // Remove the caller's agent and reclaim its rent
+    pub fn deregister_agent(ctx: Context<DeregisterAgent>) -> Result<()> {
+        let agent_key = ctx.accounts.agent_profile.key();
+        let was_active = ctx.accounts.agent_profile.is_active;
+        ctx.accounts.config.record_removal(&agent_key, was_active);
+        release_name(
+            &ctx.accounts.name_record,
+            &agent_key,
+            &ctx.accounts.authority.to_account_info(),
+        )
+    }
+
+    // Point discovery at a new HTTPS endpoint
+    pub fn update_endpoint(ctx: Context<UpdateAgent>, endpoint: String) -> Result<()> {
+        validate_endpoint(&endpoint)?;
+        ctx.accounts.agent_profile.endpoint = endpoint;
+        Ok(())
+    }
+
+    // Signal that the agent is still alive
+    pub fn heartbeat(ctx: Context<UpdateAgent>) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.last_heartbeat = Clock::get()?.unix_timestamp;
+        Ok(())
+    }
+
+    // Highlight or un-highlight a vetted agent (registry admin only)
+    pub fn set_featured(ctx: Context<AdminUpdateAgent>, featured: bool) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.featured = featured;
+
+        emit!(AgentFeaturedChanged {
+            authority: agent_profile.authority,
+            featured,
+            admin: ctx.accounts.admin.key(),
+        });
+        Ok(())
+    }
+
+    // Seed a vetted agent's reputation (registry admin only). Only allowed
+    // before the agent has any job history, so earned reputation is never overwritten
+    pub fn set_initial_reputation(
+        ctx: Context<AdminUpdateAgent>,
+        reputation_score: u64,
+    ) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        require!(agent_profile.total_jobs == 0, AgentError::AgentHasJobHistory);
+        agent_profile.reputation_score = reputation_score;
+        ctx.accounts
+            .config
+            .record_reputation(agent_profile.key(), reputation_score);
+
+        emit!(InitialReputationSet {
+            agent_profile: agent_profile.key(),
+            reputation_score,
+            admin: ctx.accounts.admin.key(),
+        });
+        Ok(())
+    }
+
+    // Close an agent that stopped heartbeating; anyone may call this
+    // Rent is returned to the agent's original authority
+    pub fn permissionless_expire_agent(ctx: Context<ExpireAgent>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let agent_profile = &ctx.accounts.agent_profile;
+        require!(config.expiry_seconds > 0, AgentError::ExpiryDisabled);
+
+        // Profiles created before heartbeats existed fall back to registration time
+        let last_seen = agent_profile.last_heartbeat.max(agent_profile.registered_at);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now.saturating_sub(last_seen) > config.expiry_seconds,
+            AgentError::AgentNotExpired
+        );
+        ctx.accounts
+            .config
+            .record_removal(&agent_profile.key(), agent_profile.is_active);
+        release_name(
+            &ctx.accounts.name_record,
+            &agent_profile.key(),
+            &ctx.accounts.authority.to_account_info(),
+        )?;
+
+        emit!(AgentExpired {
+            authority: agent_profile.authority,
+            agent_profile: agent_profile.key(),
+            last_heartbeat: last_seen,
+            expired_by: ctx.accounts.caller.key(),
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    // Compact view of an agent so clients can filter without the full profile
+    pub fn get_agent_summary(ctx: Context<GetAgent>) -> Result<AgentSummary> {
+        let agent_profile = &ctx.accounts.agent_profile;
+        Ok(AgentSummary {
+            authority: agent_profile.authority,
+            name: agent_profile.name.clone(),
+            reputation_score: agent_profile.reputation_score,
+            is_active: agent_profile.is_active,
+            base_fee: agent_profile.base_fee,
+            min_job_value: agent_profile.min_job_value,
+        })
+    }
+
+    // Aggregate counts, like the leaderboards' get_stats
+    pub fn get_registry_stats(ctx: Context<GetRegistryConfig>) -> Result<RegistryStats> {
+        let config = &ctx.accounts.config;
+        Ok(RegistryStats {
+            total_agents: config.total_agents,
+            active_agents: config.active_agents,
+        })
+    }
+
+    // Highest-reputation agents, best first
+    pub fn get_top_agents(ctx: Context<GetRegistryConfig>) -> Result<Vec<TopAgent>> {
+        Ok(ctx.accounts.config.top_agents.clone())
+    }
+
+    // Grow a RegistryConfig created before top_agents existed (anyone may pay)
+    pub fn migrate_registry_config(ctx: Context<MigrateRegistryConfig>) -> Result<()> {
+        let config = ctx.accounts.config.to_account_info();
+        if config.data_len() >= REGISTRY_CONFIG_SPACE {
+            return Ok(());
+        }
+
+        let rent_due = Rent::get()?
+            .minimum_balance(REGISTRY_CONFIG_SPACE)
+            .saturating_sub(config.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: config.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+
+        // Zeroed bytes decode as an empty top_agents list
+        config.realloc(REGISTRY_CONFIG_SPACE, true)?;
+        Ok(())
+    }
+
+    // Store the bump on a profile registered before it was recorded, so the
+    // agent's own instructions accept it again (anyone may call)
+    pub fn backfill_agent_bump(ctx: Context<BackfillAgentBump>) -> Result<()> {
+        ctx.accounts.agent_profile.bump = ctx.bumps.agent_profile;
         Ok(())
     }
 
@@ -33,6 +270,7 @@ pub mod solana_agent_registry {
         ctx: Context<UpdateAgent>,
         base_fee: u64,
         asset_mint: Pubkey,
+        min_job_value: u64,
     ) -> Result<()> {
         let agent_profile = &mut ctx.accounts.agent_profile;
         // Verify authority
@@ -40,32 +278,150 @@ pub mod solana_agent_registry {
 
         agent_profile.base_fee = base_fee;
         agent_profile.asset_mint = asset_mint;
+        agent_profile.min_job_value = min_job_value;
+
+        emit!(AgentPricingUpdated {
+            authority: agent_profile.authority,
+            base_fee,
+            asset_mint,
+            min_job_value,
+        });
         Ok(())
     }
 
     // Update agent reputation (only callable by authorized reporter/oracle)
     // Simplified: self-reporting or anyone can report for demo (In prod: protect this)
+    // The reputation increment stays pending until finalize_job runs after the dispute window
     pub fn report_job_completion(
         ctx: Context<ReportJob>,
         success: bool,
+        capability: String,
     ) -> Result<()> {
         let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.record_capability_job(&capability)?;
+        let job_id = agent_profile.total_jobs;
         agent_profile.total_jobs += 1;
-        if success {
-            // Simple reputation increment
-            agent_profile.reputation_score = agent_profile.reputation_score.saturating_add(1);
+
+        // Lock in the increment now so later config changes don't skew pending totals
+        let reputation_delta = if success { ctx.accounts.config.reputation_increment } else { 0 };
+        agent_profile.pending_reputation =
+            agent_profile.pending_reputation.saturating_add(reputation_delta);
+
+        let job_report = &mut ctx.accounts.job_report;
+        job_report.agent_profile = agent_profile.key();
+        job_report.job_id = job_id;
+        job_report.client = ctx.accounts.reporter.key();
+        job_report.success = success;
+        job_report.disputed = false;
+        job_report.reputation_delta = reputation_delta;
+        job_report.reported_at = Clock::get()?.unix_timestamp;
+        job_report.bump = ctx.bumps.job_report;
+        Ok(())
+    }
+
+    // Contest a report within the dispute window. The reporter, the agent's
+    // authority or the registry admin may dispute, so self-reported jobs can
+    // still be challenged
+    pub fn dispute_job(ctx: Context<DisputeJob>) -> Result<()> {
+        let job_report = &mut ctx.accounts.job_report;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!job_report.disputed, AgentError::AlreadyDisputed);
+        require!(
+            now <= job_report.reported_at.saturating_add(ctx.accounts.config.dispute_window),
+            AgentError::DisputeWindowClosed
+        );
+        job_report.disputed = true;
+
+        emit!(JobDisputed {
+            agent_profile: job_report.agent_profile,
+            job_id: job_report.job_id,
+            client: job_report.client,
+            timestamp: now,
+            disputed_by: ctx.accounts.disputer.key(),
+        });
+        Ok(())
+    }
+
+    // Settle a report once its dispute window has closed; anyone may call this
+    // Undisputed successes become confirmed reputation, disputed ones are dropped
+    pub fn finalize_job(ctx: Context<FinalizeJob>) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        let job_report = &ctx.accounts.job_report;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now > job_report.reported_at.saturating_add(ctx.accounts.config.dispute_window),
+            AgentError::DisputeWindowOpen
+        );
+
+        let confirmed = job_report.success && !job_report.disputed;
+        agent_profile.pending_reputation = agent_profile
+            .pending_reputation
+            .saturating_sub(job_report.reputation_delta);
+        if confirmed {
+            agent_profile.reputation_score = agent_profile
+                .reputation_score
+                .saturating_add(job_report.reputation_delta);
+            ctx.accounts
+                .config
+                .record_reputation(agent_profile.key(), agent_profile.reputation_score);
         }
+
+        emit!(JobFinalized {
+            agent_profile: job_report.agent_profile,
+            job_id: job_report.job_id,
+            confirmed,
+            timestamp: now,
+        });
         Ok(())
     }
 }
 
+// ========================= HELPERS =========================
+
+// Discovery only hands out HTTPS endpoints with a host after the scheme
+pub fn validate_endpoint(endpoint: &str) -> Result<()> {
+    require!(
+        endpoint.len() <= MAX_ENDPOINT_LEN
+            && endpoint.len() > "https://".len()
+            && endpoint.starts_with("https://")
+            && !endpoint.chars().any(|c| c.is_whitespace() || c.is_control()),
+        AgentError::InvalidEndpoint
+    );
+    Ok(())
+}
+
+// Close an agent's NameRecord so the name can be registered again. Agents
+// registered before name records existed have none, which is not an error
+pub fn release_name(
+    name_record: &UncheckedAccount,
+    agent_profile: &Pubkey,
+    rent_recipient: &AccountInfo,
+) -> Result<()> {
+    if name_record.owner != &crate::ID {
+        return Ok(());
+    }
+    let record = NameRecord::try_deserialize(&mut &name_record.try_borrow_data()?[..])?;
+    require_keys_eq!(record.agent_profile, *agent_profile, AgentError::Unauthorized);
+
+    let lamports = name_record.lamports();
+    **name_record.try_borrow_mut_lamports()? = 0;
+    **rent_recipient.try_borrow_mut_lamports()? += lamports;
+    name_record.assign(&system_program::ID);
+    name_record.realloc(0, false)?;
+    Ok(())
+}
+
 // ========================= ACCOUNTS =========================
 
 #[account]
+#[derive(InitSpace)]
 pub struct AgentProfile {
     pub authority: Pubkey,          // The agent's wallet
+    #[max_len(MAX_AGENT_NAME_LEN)]
     pub name: String,               // Agent name
+    #[max_len(MAX_ENDPOINT_LEN)]
     pub endpoint: String,           // HTTP Endpoint for interactions
+    #[max_len(MAX_CAPABILITIES, MAX_CAPABILITY_LEN)]
     pub capabilities: Vec<String>,  // Capabilities tags
     pub base_fee: u64,              // Base fee in atomic units
     pub asset_mint: Pubkey,         // Token usage (USDC)
@@ -73,21 +429,178 @@ pub struct AgentProfile {
     pub total_jobs: u64,            // Total jobs processed
     pub registered_at: i64,         // Registration timestamp
     pub is_active: bool,            // Status
+    pub last_heartbeat: i64,        // Last liveness signal
+    pub featured: bool,             // Vetted by the registry admin
+    pub pending_reputation: u64,    // Successes still inside their dispute window
+    #[max_len(MAX_CAPABILITIES)]
+    pub capability_jobs: Vec<CapabilityJobs>, // Jobs reported per advertised capability
+    pub bump: u8,                   // PDA bump (0 until set by backfill_agent_bump)
+    pub min_job_value: u64,         // Smallest job payment accepted, in asset_mint units (0 = any)
+}
+
+impl AgentProfile {
+    // Off-chain filters like "reputation >= N" use the same rule as on-chain checks
+    pub fn meets_reputation(&self, min: u64) -> bool {
+        self.reputation_score >= min
+    }
+
+    // Count a job against one of the agent's advertised capabilities. Entries
+    // are only created for advertised tags, so the list stays bounded by them
+    pub fn record_capability_job(&mut self, capability: &str) -> Result<()> {
+        require!(
+            self.capabilities.iter().any(|tag| tag == capability),
+            AgentError::CapabilityNotAdvertised
+        );
+        match self.capability_jobs.iter_mut().find(|entry| entry.capability == capability) {
+            Some(entry) => entry.jobs += 1,
+            None => self.capability_jobs.push(CapabilityJobs {
+                capability: capability.to_string(),
+                jobs: 1,
+            }),
+        }
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct JobReport {
+    pub agent_profile: Pubkey,      // Agent the job was reported for
+    pub job_id: u64,                // Agent's total_jobs at report time
+    pub client: Pubkey,             // Reporter, who receives the rent on finalize
+    pub success: bool,              // Reported outcome
+    pub disputed: bool,             // Contested within the dispute window
+    pub reported_at: i64,           // Report timestamp
+    pub bump: u8,                   // PDA bump
+    pub reputation_delta: u64,      // Reputation credited if confirmed
+}
+
+impl JobReport {
+    // The reporter, the agent itself or the registry admin
+    pub fn can_be_disputed_by(
+        &self,
+        disputer: &Pubkey,
+        agent_authority: &Pubkey,
+        admin: &Pubkey,
+    ) -> bool {
+        disputer == &self.client || disputer == agent_authority || disputer == admin
+    }
+}
+
+// Maps an exact agent name to its profile, PDA [b"agent_name", hash(name)]
+#[account]
+#[derive(InitSpace)]
+pub struct NameRecord {
+    pub agent_profile: Pubkey,      // Profile holding the name
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryConfig {
+    pub admin: Pubkey,              // Registry administrator
+    pub expiry_seconds: i64,        // Heartbeat timeout before an agent can be expired
+    pub dispute_window: i64,        // Seconds a client has to dispute a job report
+    pub reputation_increment: u64,  // Reputation per confirmed success (default 1)
+    pub bump: u8,                   // PDA bump
+    #[max_len(TOP_AGENTS_LEN)]
+    pub top_agents: Vec<TopAgent>,  // Up to TOP_AGENTS_LEN agents, sorted by reputation
+    pub total_agents: u64,          // Registered profiles
+    pub active_agents: u64,         // Registered profiles with is_active set
+    pub min_capabilities: u8,       // Fewest tags an agent may register with
+    pub max_capabilities: u8,       // Most tags an agent may register with (0 = no limit)
+}
+
+impl RegistryConfig {
+    // Re-rank an agent after its reputation changes. A full list evicts its
+    // lowest entry only when the newcomer scores strictly higher, and ties keep
+    // whoever reached the score first
+    pub fn record_reputation(&mut self, agent_profile: Pubkey, reputation_score: u64) {
+        self.remove_top_agent(&agent_profile);
+        let position = self
+            .top_agents
+            .partition_point(|entry| entry.reputation_score >= reputation_score);
+        if position < TOP_AGENTS_LEN {
+            self.top_agents.insert(position, TopAgent { agent_profile, reputation_score });
+            self.top_agents.truncate(TOP_AGENTS_LEN);
+        }
+    }
+
+    pub fn remove_top_agent(&mut self, agent_profile: &Pubkey) {
+        self.top_agents.retain(|entry| entry.agent_profile != *agent_profile);
+    }
+
+    // Trim tags and enforce the configured count range; at least one tag is
+    // always required so agents are discoverable
+    pub fn validate_capabilities(&self, capabilities: Vec<String>) -> Result<Vec<String>> {
+        let capabilities: Vec<String> = capabilities
+            .into_iter()
+            .map(|tag| tag.trim().to_string())
+            .collect();
+        require!(!capabilities.is_empty(), AgentError::NoCapabilities);
+        require!(
+            capabilities.iter().all(|tag| !tag.is_empty()),
+            AgentError::EmptyCapability
+        );
+        let count = capabilities.len();
+        require!(
+            count >= self.min_capabilities as usize
+                && (self.max_capabilities == 0 || count <= self.max_capabilities as usize),
+            AgentError::CapabilityCountOutOfRange
+        );
+        Ok(capabilities)
+    }
+
+    // Saturating: agents registered before the counters existed were never counted
+    pub fn record_removal(&mut self, agent_profile: &Pubkey, was_active: bool) {
+        self.remove_top_agent(agent_profile);
+        self.total_agents = self.total_agents.saturating_sub(1);
+        if was_active {
+            self.active_agents = self.active_agents.saturating_sub(1);
+        }
+    }
 }
 
 // ========================= CONTEXTS =========================
 
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = REGISTRY_CONFIG_SPACE,
+        seeds = [b"registry_config"],
+        bump
+    )]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 #[instruction(name: String, endpoint: String, capabilities: Vec<String>)]
 pub struct RegisterAgent<'info> {
+    #[account(mut, seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 64 + 128 + (4 + 20 * 32) + 8 + 32 + 8 + 8 + 8 + 1, // Approx space calculation
+        space = 8 + AgentProfile::INIT_SPACE,
         seeds = [b"agent_profile", authority.key().as_ref()],
         bump
     )]
     pub agent_profile: Account<'info, AgentProfile>,
+    // init_if_needed so a taken name fails with NameTaken rather than inside
+    // account creation
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + NameRecord::INIT_SPACE,
+        seeds = [b"agent_name", hash(name.as_bytes()).as_ref()],
+        bump
+    )]
+    pub name_record: Account<'info, NameRecord>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -98,18 +611,264 @@ pub struct UpdateAgent<'info> {
     #[account(
         mut,
         seeds = [b"agent_profile", authority.key().as_ref()],
-        bump,
+        bump = agent_profile.bump,
+        has_one = authority
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAgentActive<'info> {
+    #[account(mut, seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"agent_profile", authority.key().as_ref()],
+        bump = agent_profile.bump,
+        has_one = authority
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterAgent<'info> {
+    #[account(mut, seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"agent_profile", authority.key().as_ref()],
+        bump = agent_profile.bump,
         has_one = authority
     )]
     pub agent_profile: Account<'info, AgentProfile>,
+    /// CHECK: Address is fixed by the seeds; contents are checked in release_name
+    #[account(
+        mut,
+        seeds = [b"agent_name", hash(agent_profile.name.as_bytes()).as_ref()],
+        bump
+    )]
+    pub name_record: UncheckedAccount<'info>,
+    #[account(mut)]
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct ReportJob<'info> {
+    #[account(seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
     #[account(mut)]
     pub agent_profile: Account<'info, AgentProfile>,
+    #[account(
+        init,
+        payer = reporter,
+        space = 8 + JobReport::INIT_SPACE,
+        seeds = [
+            b"job_report",
+            agent_profile.key().as_ref(),
+            agent_profile.total_jobs.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub job_report: Account<'info, JobReport>,
+    #[account(mut)]
     pub reporter: Signer<'info>, // In prod, check if reporter is authorized
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeJob<'info> {
+    #[account(seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    pub agent_profile: Account<'info, AgentProfile>,
+    #[account(
+        mut,
+        has_one = agent_profile,
+        constraint = job_report.can_be_disputed_by(
+            &disputer.key(),
+            &agent_profile.authority,
+            &config.admin
+        ) @ AgentError::Unauthorized
+    )]
+    pub job_report: Account<'info, JobReport>,
+    pub disputer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeJob<'info> {
+    #[account(mut, seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+    #[account(
+        mut,
+        close = client,
+        has_one = agent_profile,
+        has_one = client
+    )]
+    pub job_report: Account<'info, JobReport>,
+    /// CHECK: Receives the report's rent; checked against job_report.client
+    #[account(mut)]
+    pub client: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRegistryConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = admin @ AgentError::Unauthorized
+    )]
+    pub config: Account<'info, RegistryConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdminUpdateAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = admin @ AgentError::Unauthorized
+    )]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetRegistryConfig<'info> {
+    #[account(seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateRegistryConfig<'info> {
+    /// CHECK: Legacy layout can't be deserialized yet; only the PDA address is checked
+    #[account(mut, seeds = [b"registry_config"], bump)]
+    pub config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BackfillAgentBump<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent_profile", agent_profile.authority.as_ref()],
+        bump
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+}
+
+#[derive(Accounts)]
+pub struct GetAgent<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireAgent<'info> {
+    #[account(mut, seeds = [b"registry_config"], bump = config.bump)]
+    pub config: Account<'info, RegistryConfig>,
+    #[account(mut, close = authority, has_one = authority)]
+    pub agent_profile: Account<'info, AgentProfile>,
+    /// CHECK: Address is fixed by the seeds; contents are checked in release_name
+    #[account(
+        mut,
+        seeds = [b"agent_name", hash(agent_profile.name.as_bytes()).as_ref()],
+        bump
+    )]
+    pub name_record: UncheckedAccount<'info>,
+    /// CHECK: Receives the reclaimed rent; checked against agent_profile.authority
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+    pub caller: Signer<'info>,
+}
+
+// ========================= EVENTS =========================
+
+#[event]
+pub struct AgentPricingUpdated {
+    pub authority: Pubkey,
+    pub base_fee: u64,
+    pub asset_mint: Pubkey,
+    pub min_job_value: u64,
+}
+
+#[event]
+pub struct JobDisputed {
+    pub agent_profile: Pubkey,
+    pub job_id: u64,
+    pub client: Pubkey,
+    pub timestamp: i64,
+    pub disputed_by: Pubkey,
+}
+
+#[event]
+pub struct JobFinalized {
+    pub agent_profile: Pubkey,
+    pub job_id: u64,
+    pub confirmed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InitialReputationSet {
+    pub agent_profile: Pubkey,
+    pub reputation_score: u64,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct AgentFeaturedChanged {
+    pub authority: Pubkey,
+    pub featured: bool,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct AgentExpired {
+    pub authority: Pubkey,
+    pub agent_profile: Pubkey,
+    pub last_heartbeat: i64,
+    pub expired_by: Pubkey,
+    pub timestamp: i64,
+}
+
+// ========================= DATA TYPES =========================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CapabilityJobs {
+    #[max_len(MAX_CAPABILITY_LEN)]
+    pub capability: String,
+    pub jobs: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RegistryStats {
+    pub total_agents: u64,
+    pub active_agents: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TopAgent {
+    pub agent_profile: Pubkey,
+    pub reputation_score: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AgentSummary {
+    pub authority: Pubkey,
+    pub name: String,
+    pub reputation_score: u64,
+    pub is_active: bool,
+    pub base_fee: u64,
+    pub min_job_value: u64,
 }
 
 // ========================= ERRORS =========================
@@ -118,4 +877,251 @@ pub struct ReportJob<'info> {
 pub enum AgentError {
     #[msg("You are not authorized to perform this action.")]
     Unauthorized,
+    #[msg("Agent expiry is disabled for this registry.")]
+    ExpiryDisabled,
+    #[msg("The agent has heartbeated within the expiry window.")]
+    AgentNotExpired,
+    #[msg("This job report has already been disputed.")]
+    AlreadyDisputed,
+    #[msg("The dispute window for this job has closed.")]
+    DisputeWindowClosed,
+    #[msg("The dispute window for this job is still open.")]
+    DisputeWindowOpen,
+    #[msg("An agent must register at least one capability.")]
+    NoCapabilities,
+    #[msg("Capability tags cannot be empty.")]
+    EmptyCapability,
+    #[msg("Number of capabilities is outside the registry's allowed range.")]
+    CapabilityCountOutOfRange,
+    #[msg("Maximum capabilities must be 0 or at least the minimum.")]
+    InvalidCapabilityLimits,
+    #[msg("The agent does not advertise this capability.")]
+    CapabilityNotAdvertised,
+    #[msg("Endpoint must be an https:// URL of at most 124 bytes.")]
+    InvalidEndpoint,
+    #[msg("Another agent has already registered this name.")]
+    NameTaken,
+    #[msg("Initial reputation can only be set before the agent has any jobs.")]
+    AgentHasJobHistory,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RegistryConfig {
+        RegistryConfig {
+            admin: Pubkey::default(),
+            expiry_seconds: 0,
+            dispute_window: 0,
+            reputation_increment: 1,
+            bump: 0,
+            top_agents: Vec::new(),
+            total_agents: 0,
+            active_agents: 0,
+            min_capabilities: 0,
+            max_capabilities: 0,
+        }
+    }
+
+    fn agent(capabilities: &[&str]) -> AgentProfile {
+        AgentProfile {
+            authority: Pubkey::new_unique(),
+            name: String::new(),
+            endpoint: String::new(),
+            capabilities: tags(capabilities),
+            base_fee: 0,
+            asset_mint: Pubkey::default(),
+            reputation_score: 0,
+            total_jobs: 0,
+            registered_at: 0,
+            is_active: true,
+            last_heartbeat: 0,
+            featured: false,
+            pending_reputation: 0,
+            capability_jobs: Vec::new(),
+            bump: 0,
+            min_job_value: 0,
+        }
+    }
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    fn ranking(config: &RegistryConfig) -> Vec<(Pubkey, u64)> {
+        config
+            .top_agents
+            .iter()
+            .map(|entry| (entry.agent_profile, entry.reputation_score))
+            .collect()
+    }
+
+    #[test]
+    fn record_reputation_keeps_descending_order_and_ties_first_come() {
+        let mut config = config();
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        config.record_reputation(a, 5);
+        config.record_reputation(b, 5);
+        config.record_reputation(c, 9);
+        assert_eq!(ranking(&config), vec![(c, 9), (a, 5), (b, 5)]);
+
+        // Re-ranking moves the entry instead of duplicating it
+        config.record_reputation(a, 1);
+        assert_eq!(ranking(&config), vec![(c, 9), (b, 5), (a, 1)]);
+    }
+
+    #[test]
+    fn record_reputation_evicts_only_for_a_strictly_higher_score() {
+        let mut config = config();
+        let agents: Vec<Pubkey> = (0..TOP_AGENTS_LEN).map(|_| Pubkey::new_unique()).collect();
+        for (i, agent) in agents.iter().enumerate() {
+            config.record_reputation(*agent, (i + 1) as u64);
+        }
+        assert_eq!(config.top_agents.len(), TOP_AGENTS_LEN);
+
+        let tied = Pubkey::new_unique();
+        config.record_reputation(tied, 1);
+        assert!(config.top_agents.iter().all(|entry| entry.agent_profile != tied));
+        assert_eq!(config.top_agents.last().unwrap().agent_profile, agents[0]);
+
+        let higher = Pubkey::new_unique();
+        config.record_reputation(higher, 2);
+        assert_eq!(config.top_agents.len(), TOP_AGENTS_LEN);
+        assert!(config.top_agents.iter().all(|entry| entry.agent_profile != agents[0]));
+        assert_eq!(ranking(&config)[TOP_AGENTS_LEN - 2..], [(agents[1], 2), (higher, 2)]);
+    }
+
+    #[test]
+    fn validate_endpoint_requires_an_https_host_without_whitespace() {
+        assert!(validate_endpoint("https://agent.example").is_ok());
+        let invalid = Err(AgentError::InvalidEndpoint.into());
+        assert_eq!(validate_endpoint("https://"), invalid);
+        assert_eq!(validate_endpoint("http://agent.example"), invalid);
+        assert_eq!(validate_endpoint("https://agent .example"), invalid);
+        assert_eq!(validate_endpoint("https://agent.example\n"), invalid);
+
+        let longest = format!("https://{}", "a".repeat(MAX_ENDPOINT_LEN - "https://".len()));
+        assert!(validate_endpoint(&longest).is_ok());
+        assert_eq!(validate_endpoint(&format!("{longest}a")), invalid);
+    }
+
+    #[test]
+    fn validate_capabilities_trims_tags_and_rejects_empty_ones() {
+        let config = config();
+        assert_eq!(
+            config.validate_capabilities(tags(&[" coaching ", "pullups\t"])),
+            Ok(tags(&["coaching", "pullups"]))
+        );
+        let empty = Err(AgentError::EmptyCapability.into());
+        assert_eq!(config.validate_capabilities(tags(&["coaching", "  "])), empty);
+        let none = Err(AgentError::NoCapabilities.into());
+        assert_eq!(config.validate_capabilities(Vec::new()), none);
+    }
+
+    #[test]
+    fn validate_capabilities_enforces_the_configured_range() {
+        let mut config = config();
+        config.min_capabilities = 2;
+        config.max_capabilities = 3;
+        let out_of_range = Err(AgentError::CapabilityCountOutOfRange.into());
+        assert_eq!(config.validate_capabilities(tags(&["a"])), out_of_range);
+        assert!(config.validate_capabilities(tags(&["a", "b"])).is_ok());
+        assert!(config.validate_capabilities(tags(&["a", "b", "c"])).is_ok());
+        assert_eq!(config.validate_capabilities(tags(&["a", "b", "c", "d"])), out_of_range);
+
+        // 0 means no upper limit, and a 0 minimum still needs one tag
+        config.min_capabilities = 0;
+        config.max_capabilities = 0;
+        let many: Vec<String> = (0..MAX_CAPABILITIES).map(|i| i.to_string()).collect();
+        assert!(config.validate_capabilities(many).is_ok());
+        let none = Err(AgentError::NoCapabilities.into());
+        assert_eq!(config.validate_capabilities(Vec::new()), none);
+    }
+
+    #[test]
+    fn record_capability_job_counts_only_advertised_tags() {
+        let mut agent = agent(&["coaching", "pullups"]);
+        agent.record_capability_job("pullups").unwrap();
+        agent.record_capability_job("pullups").unwrap();
+        agent.record_capability_job("coaching").unwrap();
+
+        let unadvertised = Err(AgentError::CapabilityNotAdvertised.into());
+        assert_eq!(agent.record_capability_job("jumps"), unadvertised);
+        let counts: Vec<(&str, u64)> = agent
+            .capability_jobs
+            .iter()
+            .map(|entry| (entry.capability.as_str(), entry.jobs))
+            .collect();
+        assert_eq!(counts, vec![("pullups", 2), ("coaching", 1)]);
+    }
+
+    #[test]
+    fn record_removal_saturates_for_uncounted_agents() {
+        let mut config = config();
+        config.total_agents = 2;
+        config.active_agents = 1;
+        let inactive = Pubkey::new_unique();
+        config.record_reputation(inactive, 3);
+        config.record_removal(&inactive, false);
+        assert_eq!((config.total_agents, config.active_agents), (1, 1));
+        assert!(config.top_agents.is_empty());
+
+        // Agents registered before the counters existed never bumped them
+        for _ in 0..3 {
+            config.record_removal(&Pubkey::new_unique(), true);
+        }
+        assert_eq!((config.total_agents, config.active_agents), (0, 0));
+    }
+
+    #[test]
+    fn record_reputation_reranks_members_of_a_full_list_without_eviction() {
+        let mut config = config();
+        let agents: Vec<Pubkey> = (0..TOP_AGENTS_LEN).map(|_| Pubkey::new_unique()).collect();
+        for agent in &agents {
+            config.record_reputation(*agent, 10);
+        }
+
+        // A member dropping below everyone keeps its place at the bottom
+        config.record_reputation(agents[0], 0);
+        assert_eq!(config.top_agents.len(), TOP_AGENTS_LEN);
+        assert_eq!(ranking(&config)[TOP_AGENTS_LEN - 1], (agents[0], 0));
+
+        // Returning to a tied score lands behind the agents already there
+        config.record_reputation(agents[0], 10);
+        assert_eq!(ranking(&config)[TOP_AGENTS_LEN - 1], (agents[0], 10));
+
+        // A newcomer tying the lowest entry of a full list is not admitted
+        let newcomer = Pubkey::new_unique();
+        config.record_reputation(newcomer, 10);
+        assert!(config.top_agents.iter().all(|entry| entry.agent_profile != newcomer));
+    }
+
+    #[test]
+    fn job_reports_can_be_disputed_by_reporter_agent_or_admin() {
+        let (agent_authority, admin) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let report = |client| JobReport {
+            agent_profile: Pubkey::new_unique(),
+            job_id: 0,
+            client,
+            success: true,
+            disputed: false,
+            reported_at: 0,
+            bump: 0,
+            reputation_delta: 1,
+        };
+        let client_report = report(Pubkey::new_unique());
+        let disputable = |report: &JobReport, disputer: &Pubkey| {
+            report.can_be_disputed_by(disputer, &agent_authority, &admin)
+        };
+        assert!(disputable(&client_report, &client_report.client));
+        assert!(disputable(&client_report, &agent_authority));
+        assert!(disputable(&client_report, &admin));
+        assert!(!disputable(&client_report, &Pubkey::new_unique()));
+
+        // A self-reported job can still be contested by the admin
+        let self_report = report(agent_authority);
+        assert!(disputable(&self_report, &admin));
+        assert!(!disputable(&self_report, &Pubkey::new_unique()));
+    }
 }
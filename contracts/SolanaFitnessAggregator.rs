@@ -0,0 +1,296 @@
+// Solana Fitness Aggregator Program
+// Read-only view combining a user's pullups and jumps leaderboard scores under
+// an admin-configured formula
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"); // Replace with the deployed ID
+
+// Leaderboard programs whose UserScore accounts this program reads
+pub const PULLUPS_PROGRAM_ID: Pubkey = pubkey!("GDSkDgf6Q5mMN5kHZiKTXaAs2CLAkopDRDkSCM1tpcQa");
+pub const JUMPS_PROGRAM_ID: Pubkey = pubkey!("7ugCR1KLjHNgUjbW1pZGCadeCHKvUu7NwXsXDTTFypUd");
+
+// Form scores are percentages from the coaching model
+pub const MAX_FORM_SCORE: u8 = 100;
+
+#[program]
+pub mod solana_fitness_aggregator {
+    use super::*;
+
+    // Create the config holding the ranking formula. Until it exists every
+    // ranking uses the default Sum
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        formula: ScoringFormula,
+        form_oracle: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.formula = formula;
+        config.form_oracle = form_oracle;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    // Change the formula and the form oracle (admin only)
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        formula: ScoringFormula,
+        form_oracle: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.formula = formula;
+        config.form_oracle = form_oracle;
+        Ok(())
+    }
+
+    // Overall score for one user across both exercises, using the configured
+    // formula. form_score is only read by QualityAdjusted, which needs the
+    // form oracle to co-sign so a caller can't inflate it
+    pub fn combined_rank(ctx: Context<CombinedRank>, form_score: u8) -> Result<CombinedScore> {
+        let pullups = read_user_score(&ctx.accounts.pullups_score, &PULLUPS_PROGRAM_ID)?;
+        let jumps = read_user_score(&ctx.accounts.jumps_score, &JUMPS_PROGRAM_ID)?;
+        require_keys_eq!(pullups.user, jumps.user, AggregatorError::UserMismatch);
+
+        let formula = ctx
+            .accounts
+            .config
+            .as_ref()
+            .map(|config| config.formula)
+            .unwrap_or_default();
+        if matches!(formula, ScoringFormula::QualityAdjusted) {
+            require!(ctx.accounts.form_oracle.is_some(), AggregatorError::FormOracleRequired);
+        }
+        let combined_score = compute_score(formula, &pullups, &jumps, form_score)?;
+
+        Ok(CombinedScore {
+            user: pullups.user,
+            pullups_total: pullups.total_score,
+            jumps_total: jumps.total_score,
+            combined_score,
+        })
+    }
+}
+
+// ========================= HELPERS =========================
+
+// Every formula goes through here so variants stay comparable
+pub fn compute_score(
+    formula: ScoringFormula,
+    pullups: &UserScorePrefix,
+    jumps: &UserScorePrefix,
+    form_score: u8,
+) -> Result<u64> {
+    let sum = || {
+        pullups
+            .total_score
+            .checked_add(jumps.total_score)
+            .ok_or(AggregatorError::ArithmeticOverflow)
+    };
+    let score = match formula {
+        ScoringFormula::Sum => sum()?,
+        ScoringFormula::BestSingle => pullups.best_single_score.max(jumps.best_single_score),
+        ScoringFormula::Weighted { pullup_weight, jump_weight } => pullups
+            .total_score
+            .checked_mul(pullup_weight)
+            .zip(jumps.total_score.checked_mul(jump_weight))
+            .and_then(|(p, j)| p.checked_add(j))
+            .ok_or(AggregatorError::ArithmeticOverflow)?,
+        ScoringFormula::QualityAdjusted => {
+            require!(form_score <= MAX_FORM_SCORE, AggregatorError::InvalidFormScore);
+            (sum()? as u128 * form_score as u128 / MAX_FORM_SCORE as u128) as u64
+        }
+    };
+    Ok(score)
+}
+
+// Both leaderboards name the account UserScore, so they share a discriminator
+fn user_score_discriminator() -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(b"account:UserScore").to_bytes()[..8]);
+    discriminator
+}
+
+// Decode the leading fields of a leaderboard UserScore owned by `program_id`
+fn read_user_score(account: &UncheckedAccount, program_id: &Pubkey) -> Result<UserScorePrefix> {
+    require_keys_eq!(*account.owner, *program_id, AggregatorError::WrongProgram);
+
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == user_score_discriminator(),
+        AggregatorError::NotAUserScore
+    );
+    Ok(UserScorePrefix::deserialize(&mut &data[8..])?)
+}
+
+// ========================= CONTEXTS =========================
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AggregatorConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, AggregatorConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ AggregatorError::Unauthorized
+    )]
+    pub config: Account<'info, AggregatorConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CombinedRank<'info> {
+    /// CHECK: Owner and discriminator are verified in read_user_score
+    pub pullups_score: UncheckedAccount<'info>,
+    /// CHECK: Owner and discriminator are verified in read_user_score
+    pub jumps_score: UncheckedAccount<'info>,
+    // Absent until initialize_config runs; the formula then defaults to Sum
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Option<Account<'info, AggregatorConfig>>,
+    // Required by QualityAdjusted to vouch for form_score
+    #[account(
+        constraint = config.as_ref().is_some_and(|config| config.form_oracle == form_oracle.key())
+            @ AggregatorError::Unauthorized
+    )]
+    pub form_oracle: Option<Signer<'info>>,
+}
+
+// ========================= DATA TYPES =========================
+
+#[account]
+#[derive(InitSpace)]
+pub struct AggregatorConfig {
+    pub admin: Pubkey,                  // May change the formula and oracle
+    pub formula: ScoringFormula,        // Used by every combined_rank
+    pub form_oracle: Pubkey,            // Co-signs form scores for QualityAdjusted
+    pub bump: u8,                       // PDA bump
+}
+
+// Stable prefix of both leaderboards' UserScore; later fields are not read
+#[derive(AnchorDeserialize)]
+pub struct UserScorePrefix {
+    pub user: Pubkey,
+    pub total_score: u64,
+    pub best_single_score: u64,
+    pub submission_count: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CombinedScore {
+    pub user: Pubkey,
+    pub pullups_total: u64,
+    pub jumps_total: u64,
+    pub combined_score: u64,            // Result of compute_score for the configured formula
+}
+
+// How the two exercises combine into one ranking score
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub enum ScoringFormula {
+    #[default]
+    Sum,                                // pullups + jumps totals
+    BestSingle,                         // Better of the two best single sessions
+    Weighted { pullup_weight: u64, jump_weight: u64 },
+    QualityAdjusted,                    // Sum scaled by form_score / 100
+}
+
+// ========================= ERRORS =========================
+
+#[error_code]
+pub enum AggregatorError {
+    #[msg("Account is not owned by the expected leaderboard program.")]
+    WrongProgram,
+    #[msg("Account is not a UserScore.")]
+    NotAUserScore,
+    #[msg("The two scores belong to different users.")]
+    UserMismatch,
+    #[msg("Arithmetic overflow.")]
+    ArithmeticOverflow,
+    #[msg("Form score must be between 0 and 100.")]
+    InvalidFormScore,
+    #[msg("Only the config admin or form oracle can do this.")]
+    Unauthorized,
+    #[msg("QualityAdjusted ranking needs the form oracle to sign.")]
+    FormOracleRequired,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_score(total_score: u64, best_single_score: u64) -> UserScorePrefix {
+        UserScorePrefix {
+            user: Pubkey::default(),
+            total_score,
+            best_single_score,
+            submission_count: 1,
+        }
+    }
+
+    fn score(formula: ScoringFormula, pullups: u64, jumps: u64, form: u8) -> Result<u64> {
+        compute_score(formula, &user_score(pullups, 0), &user_score(jumps, 0), form)
+    }
+
+    fn overflow() -> Result<u64> {
+        Err(AggregatorError::ArithmeticOverflow.into())
+    }
+
+    #[test]
+    fn default_formula_is_the_plain_sum() {
+        assert_eq!(score(ScoringFormula::default(), 10, 20, 0), Ok(30));
+    }
+
+    #[test]
+    fn sum_adds_totals_and_checks_overflow() {
+        assert_eq!(score(ScoringFormula::Sum, 10, 20, 0), Ok(30));
+        assert_eq!(score(ScoringFormula::Sum, u64::MAX, 1, 0), overflow());
+    }
+
+    #[test]
+    fn best_single_takes_the_better_session() {
+        let formula = ScoringFormula::BestSingle;
+        assert_eq!(compute_score(formula, &user_score(10, 4), &user_score(20, 9), 0), Ok(9));
+        // Totals are not added, so they can't overflow
+        let (pullups, jumps) = (user_score(u64::MAX, u64::MAX), user_score(u64::MAX, 1));
+        assert_eq!(compute_score(formula, &pullups, &jumps, 0), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn weighted_scales_each_total_and_checks_overflow() {
+        let formula = ScoringFormula::Weighted { pullup_weight: 2, jump_weight: 3 };
+        assert_eq!(score(formula, 10, 20, 0), Ok(80));
+        assert_eq!(score(formula, u64::MAX / 2 + 1, 0, 0), overflow());
+        assert_eq!(score(formula, 0, u64::MAX / 3 + 1, 0), overflow());
+        let formula = ScoringFormula::Weighted { pullup_weight: 1, jump_weight: 1 };
+        assert_eq!(score(formula, u64::MAX, 1, 0), overflow());
+    }
+
+    #[test]
+    fn quality_adjusted_scales_the_sum_by_form() {
+        let formula = ScoringFormula::QualityAdjusted;
+        assert_eq!(score(formula, 150, 50, MAX_FORM_SCORE), Ok(200));
+        assert_eq!(score(formula, 150, 50, 50), Ok(100));
+        assert_eq!(score(formula, 150, 50, 0), Ok(0));
+        assert_eq!(
+            score(formula, 150, 50, MAX_FORM_SCORE + 1),
+            Err(AggregatorError::InvalidFormScore.into())
+        );
+        // Scaling is done in u128, so a full u64 sum doesn't overflow
+        assert_eq!(score(formula, u64::MAX, 0, 99), Ok(18_262_276_632_972_456_098));
+        assert_eq!(score(formula, u64::MAX, 1, 50), overflow());
+    }
+}
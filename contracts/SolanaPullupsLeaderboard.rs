@@ -2,19 +2,204 @@
 // Mirrors ExerciseLeaderboard.sol structure for pullups exercise specifically
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    hash::hash,
+    instruction::{get_stack_height, AccountMeta, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT},
+    program::invoke_signed,
+    sysvar::instructions::{self as instructions_sysvar, get_instruction_relative},
+};
+use anchor_lang::system_program;
 
 declare_id!("GDSkDgf6Q5mMN5kHZiKTXaAs2CLAkopDRDkSCM1tpcQa");
 
+// Number of client nonces remembered per user for retry dedup
+pub const RECENT_NONCES_LEN: usize = 4;
+// Number of recent (score, timestamp) pairs kept for the dedupe window
+pub const RECENT_SUBMISSIONS_LEN: usize = 4;
+
+// Bytes reserved for a UserScore account; bump when the layout grows and
+// have existing users call migrate_user_score. Unlike other accounts,
+// UserScore and Leaderboard are created at their reserved size rather than
+// 8 + INIT_SPACE, so appended fields fit without a realloc
+pub const USER_SCORE_SPACE: usize = 8 + 768;
+// Same for Leaderboard, resized with migrate_leaderboard
+pub const LEADERBOARD_SPACE: usize = 8 + 512;
+
+// Fail the build if a layout outgrows its reservation
+const _: () = assert!(8 + UserScore::INIT_SPACE <= USER_SCORE_SPACE);
+const _: () = assert!(8 + Leaderboard::INIT_SPACE <= LEADERBOARD_SPACE);
+
+// SolanaAgentRegistry, whose AgentProfile accounts can endorse a submission
+pub const AGENT_REGISTRY_PROGRAM_ID: Pubkey = pubkey!("9u4eVWRf8a7vMDCHsguakB6vxcnCuJssBVBbQAYrKdog");
+
+// Scores are fixed-point integers with at most this many decimal places
+pub const MAX_SCORE_DECIMALS: u8 = 9;
+// Single-submission cap for boards that leave max_single_submission unset
+pub const DEFAULT_MAX_SINGLE_SUBMISSION: u32 = 10_000;
+
+pub const SECONDS_PER_DAY: u64 = 86_400;
+pub const SECONDS_PER_WEEK: u64 = 7 * SECONDS_PER_DAY;
+
+// Days covered by the rolling activity buckets on UserScore
+pub const ROLLING_DAYS: usize = 7;
+
+// Streak schedules are one bit per weekday, bit 0 = Monday
+pub const ALL_TRAINING_DAYS: u8 = 0b0111_1111;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+pub const MAX_EXERCISE_NAME_LEN: usize = 32;
+
+// Participants per ParticipantIndex page; a full page of pubkeys still fits
+// in the 1024-byte return data limit used by get_participant_page
+pub const PARTICIPANT_PAGE_SIZE: usize = 30;
+// Recent submissions kept in the optional ActivityLog ring buffer
+pub const ACTIVITY_LOG_LEN: usize = 50;
+
+// How long a ProofRecord must be kept before the owner can reclaim its rent
+pub const PROOF_RETENTION_SECONDS: u64 = 90 * SECONDS_PER_DAY;
+pub const PROOF_RECORD_SPACE: usize = 8 + ProofRecord::INIT_SPACE;
+
+// Shortest retention the owner may configure before archiving idle accounts
+pub const MIN_ARCHIVE_RETENTION_SECONDS: u64 = 180 * SECONDS_PER_DAY;
+
+// Wait between requesting and completing an unfreeze when the leaderboard enforces it
+pub const UNFREEZE_DELAY_SECONDS: u64 = SECONDS_PER_DAY;
+
+// Bodyweight floor for normalized scoring so very low entries can't game it
+pub const MIN_NORMALIZED_BODYWEIGHT_KG: u16 = 40;
+pub const MAX_BODYWEIGHT_KG: u16 = 400;
+
+// Age-grading brackets per leaderboard, and the earliest accepted birth year
+pub const AGE_GRADE_TABLE_LEN: usize = 4;
+pub const MIN_BIRTH_YEAR: u16 = 1900;
+
+// Weighted pullups: heaviest accepted load, and how much each kg of it
+// raises the load-adjusted score (2 => +20 kg counts reps at 140%)
+pub const MAX_ADDED_WEIGHT_KG: u16 = 100;
+pub const LOAD_PERCENT_PER_KG: u64 = 2;
+// Sets-and-reps breakdown accepted by submit_score
+pub const MAX_SETS: usize = 10;
+pub const MAX_REPS_PER_SET: u16 = 100;
+// Lifetime total_score thresholds that emit MilestoneReached
+pub const MILESTONE_COUNT: usize = 5;
+pub const DEFAULT_MILESTONES: [u64; MILESTONE_COUNT] = [100, 500, 1_000, 5_000, 10_000];
+
+// Strict-mode requirement bits for Leaderboard.strict_flags, checked in
+// validate_submission. REQUIRE_ATTESTATION accepts only submit_signed_score.
+// Duration checks have no on-chain data yet, so that bit is rejected
+pub const REQUIRE_ATTESTATION: u32 = 1 << 0;
+pub const REQUIRE_PROOF_HASH: u32 = 1 << 1;
+pub const REQUIRE_DURATION: u32 = 1 << 2;
+pub const REQUIRE_REGISTRATION: u32 = 1 << 3;
+pub const ENFORCE_RATE_LIMITS: u32 = 1 << 4;
+pub const ENFORCE_COOLDOWN: u32 = 1 << 5;
+pub const SUPPORTED_STRICT_FLAGS: u32 = REQUIRE_ATTESTATION
+    | REQUIRE_PROOF_HASH
+    | REQUIRE_REGISTRATION
+    | ENFORCE_RATE_LIMITS
+    | ENFORCE_COOLDOWN;
+
+// Fixed bucket count for the optional best_single_score histogram
+pub const HISTOGRAM_BUCKETS: usize = 32;
+
+// Attestation nonces tracked per user in a sliding bitmap
+pub const NONCE_WINDOW_BITS: u64 = 256;
+
+pub const MAX_GYM_NAME_LEN: usize = 32;
+
+// Admin council size; proposals reserve room for the largest AdminAction
+pub const MAX_COUNCIL_OWNERS: usize = 10;
+// Bounds for an AdminAction::Invoke proposal
+pub const MAX_PROPOSAL_ACCOUNTS: usize = 12;
+pub const MAX_PROPOSAL_DATA_LEN: usize = 256;
+
+// Bumped whenever instructions or account layouts change
+pub const PROGRAM_VERSION: u16 = 1;
+
+// Optional capabilities reported by get_program_info
+pub const FEATURE_CPI_SUBMISSIONS: u64 = 1 << 0;
+pub const FEATURE_ORACLE_SIGNATURES: u64 = 1 << 1;
+pub const FEATURE_ADMIN_COUNCIL: u64 = 1 << 2;
+pub const FEATURE_REGISTRATION: u64 = 1 << 3;
+pub const FEATURE_HISTOGRAM: u64 = 1 << 4;
+pub const FEATURE_REGIONS: u64 = 1 << 5;
+pub const FEATURE_GYMS: u64 = 1 << 6;
+pub const FEATURE_COACH_ENDORSEMENT: u64 = 1 << 7;
+pub const FEATURES: u64 = FEATURE_CPI_SUBMISSIONS
+    | FEATURE_ORACLE_SIGNATURES
+    | FEATURE_ADMIN_COUNCIL
+    | FEATURE_REGISTRATION
+    | FEATURE_HISTOGRAM
+    | FEATURE_REGIONS
+    | FEATURE_GYMS
+    | FEATURE_COACH_ENDORSEMENT;
+
 #[program]
 pub mod solana_pullups_leaderboard {
     use super::*;
 
-    // Initialize a new pullups leaderboard
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    // Initialize the official pullups leaderboard, the PDA at
+    // [b"leaderboard", b"pullups"]. Further competitions come from clone_leaderboard
+    pub fn initialize(ctx: Context<Initialize>, config: LeaderboardConfig) -> Result<()> {
         let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.is_initialized = true;
         leaderboard.exercise_name = "pullups".to_string();
         leaderboard.total_participants = 0;
         leaderboard.total_submissions = 0;
+        leaderboard.owner = ctx.accounts.owner.key();
+        leaderboard.bump = ctx.bumps.leaderboard;
+        leaderboard.apply_config(&config)?;
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.leaderboard = leaderboard.key();
+        treasury.bump = ctx.bumps.treasury;
+
+        emit!(LeaderboardInitialized {
+            leaderboard: leaderboard.key(),
+            exercise_name: leaderboard.exercise_name.clone(),
+            owner: leaderboard.owner,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+        Ok(())
+    }
+
+    // Start a new competition with an existing board's settings. Counters,
+    // the treasury and the waitlist start empty; the caller becomes owner
+    pub fn clone_leaderboard(
+        ctx: Context<CloneLeaderboard>,
+        starts_at: u64,
+        end_time: u64,
+    ) -> Result<()> {
+        let source = &ctx.accounts.source;
+        let mut config = source.config();
+        config.starts_at = starts_at;
+        config.end_time = end_time;
+
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.is_initialized = true;
+        leaderboard.exercise_name = source.exercise_name.clone();
+        leaderboard.owner = ctx.accounts.owner.key();
+        leaderboard.apply_config(&config)?;
+        leaderboard.age_grades = source.age_grades;
+        leaderboard.milestones = source.milestones;
+
+        let treasury = &mut ctx.accounts.treasury;
+        treasury.leaderboard = leaderboard.key();
+        treasury.bump = ctx.bumps.treasury;
+
+        // Indexers that track boards by LeaderboardInitialized see clones too
+        emit!(LeaderboardInitialized {
+            leaderboard: leaderboard.key(),
+            exercise_name: leaderboard.exercise_name.clone(),
+            owner: leaderboard.owner,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+        emit!(LeaderboardCloned {
+            source: source.key(),
+            clone: leaderboard.key(),
+        });
         Ok(())
     }
 
@@ -22,151 +207,5473 @@ pub mod solana_pullups_leaderboard {
     pub fn submit_score(
         ctx: Context<SubmitScore>,
         score: u32,
+        client_nonce: u64,
+        referrer: Pubkey,
+        proof_hash: [u8; 32],
+        variant: PullupVariant,
+        added_weight_kg: u16,
+        sets: Vec<u16>,
+    ) -> Result<()> {
+        validate_sets(&sets, score)?;
+        process_submission(
+            ctx,
+            score,
+            client_nonce,
+            referrer,
+            proof_hash,
+            Pubkey::default(),
+            false,
+            variant,
+            added_weight_kg,
+            sets,
+        )
+    }
+
+    // Submit on behalf of a PDA owned by an allow-listed program, called via CPI.
+    // The caller signs for its PDA with invoke_signed; the PDA is the `user`
+    // and pays for its own UserScore, so it must be a funded system account.
+    pub fn submit_score_via_cpi(
+        ctx: Context<SubmitScore>,
+        score: u32,
+        client_nonce: u64,
+        referrer: Pubkey,
+        proof_hash: [u8; 32],
+    ) -> Result<()> {
+        let calling_program = verify_cpi_caller(&ctx)?;
+        process_submission(
+            ctx,
+            score,
+            client_nonce,
+            referrer,
+            proof_hash,
+            calling_program,
+            false,
+            PullupVariant::Strict,
+            0,
+            Vec::new(),
+        )
+    }
+
+    // Submit a score judged and signed off-chain by the leaderboard oracle. The
+    // instruction immediately before this one must be an Ed25519 program
+    // verification of signed_score_message by leaderboard.oracle
+    pub fn submit_signed_score(
+        ctx: Context<SubmitScore>,
+        score: u32,
+        client_nonce: u64,
+        referrer: Pubkey,
+        proof_hash: [u8; 32],
     ) -> Result<()> {
+        let message = signed_score_message(
+            &ctx.accounts.leaderboard.key(),
+            &ctx.accounts.user.key(),
+            score,
+            client_nonce,
+        );
+        verify_oracle_signature(&ctx, &message)?;
+
+        // Each signed message is single-use; the tracker tolerates
+        // out-of-order delivery within its window
+        let nonce_tracker = ctx
+            .accounts
+            .nonce_tracker
+            .as_mut()
+            .ok_or(LeaderboardError::NonceTrackerRequired)?;
+        nonce_tracker.leaderboard = ctx.accounts.leaderboard.key();
+        nonce_tracker.user = ctx.accounts.user.key();
+        nonce_tracker.bump = ctx.bumps.nonce_tracker.unwrap_or_default();
+        nonce_tracker.mark(client_nonce)?;
+
+        process_submission(
+            ctx,
+            score,
+            client_nonce,
+            referrer,
+            proof_hash,
+            Pubkey::default(),
+            true,
+            PullupVariant::Strict,
+            0,
+            Vec::new(),
+        )
+    }
+
+    // Allow an external program to submit for its PDAs (owner only)
+    pub fn allow_cpi_program(ctx: Context<AllowCpiProgram>, program_id: Pubkey) -> Result<()> {
+        let cpi_program = &mut ctx.accounts.cpi_program;
+        cpi_program.leaderboard = ctx.accounts.leaderboard.key();
+        cpi_program.program_id = program_id;
+        cpi_program.bump = ctx.bumps.cpi_program;
+        Ok(())
+    }
+
+    // Remove an external program from the CPI allow-list (owner only)
+    pub fn revoke_cpi_program(_ctx: Context<RevokeCpiProgram>, _program_id: Pubkey) -> Result<()> {
+        Ok(())
+    }
+
+    // Rename the leaderboard's exercise (owner only). The name is bounded by
+    // MAX_EXERCISE_NAME_LEN, which the reserved LEADERBOARD_SPACE already
+    // covers, so the account never needs to grow
+    pub fn update_exercise_name(ctx: Context<UpdateExerciseName>, new_name: String) -> Result<()> {
+        let new_name = normalize_exercise_name(&new_name)?;
+
         let leaderboard = &mut ctx.accounts.leaderboard;
+        require!(
+            !(leaderboard.lock_name_after_first_submission && leaderboard.all_time_submissions() > 0),
+            LeaderboardError::ExerciseNameLocked
+        );
+
+        let old = std::mem::replace(&mut leaderboard.exercise_name, new_name.clone());
+        emit!(ExerciseRenamed { old, new: new_name });
+        Ok(())
+    }
+
+    // Reclaim rent from a proof record once the retention window has passed (owner only)
+    pub fn close_proof_record(ctx: Context<CloseProofRecord>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(
+            now.saturating_sub(ctx.accounts.proof_record.timestamp) >= PROOF_RETENTION_SECONDS,
+            LeaderboardError::ProofRetentionActive
+        );
+        Ok(())
+    }
+
+    // Permissionless crank: apply any pending activity decay to a user
+    pub fn apply_activity_decay(ctx: Context<ApplyActivityDecay>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp as u64;
+        let decay_bps = ctx.accounts.leaderboard.activity_decay_bps;
+        ctx.accounts.user_score.decay_activity(decay_bps, now);
+        Ok(())
+    }
+
+    // Create the optional recent-activity log for this leaderboard (owner only)
+    pub fn initialize_activity_log(ctx: Context<InitializeActivityLog>) -> Result<()> {
+        ctx.accounts.activity_log.load_init()?.leaderboard = ctx.accounts.leaderboard.key();
+        Ok(())
+    }
+
+    // Create the optional best-score histogram (owner only). lower_bounds[i] is
+    // the smallest best_single_score counted in bucket i; index 0 must be 0
+    pub fn initialize_histogram(
+        ctx: Context<InitializeHistogram>,
+        lower_bounds: [u64; HISTOGRAM_BUCKETS],
+    ) -> Result<()> {
+        require!(
+            lower_bounds[0] == 0 && lower_bounds.windows(2).all(|w| w[0] < w[1]),
+            LeaderboardError::InvalidHistogramBounds
+        );
+
+        let histogram = &mut ctx.accounts.histogram;
+        histogram.leaderboard = ctx.accounts.leaderboard.key();
+        histogram.lower_bounds = lower_bounds;
+        histogram.bump = ctx.bumps.histogram;
+
+        ctx.accounts.leaderboard.has_histogram = true;
+        Ok(())
+    }
+
+    // Grant a wallet the moderator marker for this leaderboard (owner only)
+    pub fn add_moderator(ctx: Context<AddModerator>, moderator: Pubkey) -> Result<()> {
+        let marker = &mut ctx.accounts.moderator_marker;
+        marker.leaderboard = ctx.accounts.leaderboard.key();
+        marker.moderator = moderator;
+        marker.bump = ctx.bumps.moderator_marker;
+
+        emit!(ModeratorAdded {
+            leaderboard: marker.leaderboard,
+            moderator,
+        });
+        Ok(())
+    }
+
+    // Revoke a moderator marker (owner only); access ends immediately
+    pub fn remove_moderator(ctx: Context<RemoveModerator>, moderator: Pubkey) -> Result<()> {
+        emit!(ModeratorRemoved {
+            leaderboard: ctx.accounts.leaderboard.key(),
+            moderator,
+        });
+        Ok(())
+    }
+
+    // Flag one of a user's submissions for review (moderators only)
+    pub fn flag_submission(
+        ctx: Context<FlagSubmission>,
+        user: Pubkey,
+        submission_index: u64,
+        reason: u8,
+    ) -> Result<()> {
         let user_score = &mut ctx.accounts.user_score;
-        let user_pubkey = ctx.accounts.user.key();
+        require!(
+            submission_index < user_score.submission_count,
+            LeaderboardError::InvalidSubmissionIndex
+        );
+        user_score.open_flags = user_score
+            .open_flags
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
 
-        let score_u64 = score as u64;
-        let is_new_user = user_score.submission_count == 0;
+        let now = Clock::get()?.unix_timestamp as u64;
+        let flag = &mut ctx.accounts.flag;
+        flag.leaderboard = ctx.accounts.leaderboard.key();
+        flag.user = user;
+        flag.submission_index = submission_index;
+        flag.reason = reason;
+        flag.moderator = ctx.accounts.moderator.key();
+        flag.created_at = now;
+        flag.bump = ctx.bumps.flag;
 
-        // Update user score data
-        user_score.user = user_pubkey;
-        user_score.total_score += score_u64;
-        user_score.submission_count += 1;
-        user_score.last_submission_time = Clock::get()?.unix_timestamp as u64;
+        emit!(SubmissionFlagged {
+            user,
+            submission_index,
+            reason,
+            moderator: flag.moderator,
+            timestamp: now,
+        });
+        Ok(())
+    }
 
-        if is_new_user {
-            user_score.first_submission_time = Clock::get()?.unix_timestamp as u64;
-            leaderboard.total_participants += 1;
-        }
+    // Resolve a flag (owner or moderator). An upheld flag reverses `reversal_amount`
+    // of the flagged submission's score; a dismissed flag is simply cleared.
+    pub fn resolve_flag(ctx: Context<ResolveFlag>, valid: bool, reversal_amount: u64) -> Result<()> {
+        require_moderation_rights(
+            &ctx.accounts.leaderboard,
+            &ctx.accounts.authority,
+            &ctx.accounts.moderator_marker,
+        )?;
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let user_score = &mut ctx.accounts.user_score;
+        let flag = &ctx.accounts.flag;
 
-        // Update best single score
-        if score_u64 > user_score.best_single_score {
-            user_score.best_single_score = score_u64;
-        }
+        user_score.open_flags = user_score.open_flags.saturating_sub(1);
+        let reversed_amount = if valid {
+            let old_best = user_score.best_single_score;
+            let reversed = reverse_submission(leaderboard, user_score, reversal_amount)?;
+            sync_best_score(leaderboard, user_score, &mut ctx.accounts.histogram, old_best)?;
+            reversed
+        } else {
+            0
+        };
 
-        leaderboard.total_submissions += 1;
+        emit!(FlagResolved {
+            user: flag.user,
+            submission_index: flag.submission_index,
+            valid,
+            reversed_amount,
+            resolver: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+        Ok(())
+    }
 
-        // Emit event
-        emit!(ScoreSubmitted {
-            user: user_pubkey,
-            score_added: score_u64,
+    // Claw back a fraudulent submission detected post-hoc (oracle only)
+    pub fn revoke_submission(ctx: Context<RevokeSubmission>, amount: u64, reason: u8) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let user_score = &mut ctx.accounts.user_score;
+        let old_best = user_score.best_single_score;
+        let revoked_amount = reverse_submission(leaderboard, user_score, amount)?;
+        sync_best_score(leaderboard, user_score, &mut ctx.accounts.histogram, old_best)?;
+
+        emit!(SubmissionRevoked {
+            user: user_score.user,
+            amount: revoked_amount,
+            reason,
+            oracle: ctx.accounts.oracle.key(),
             new_total_score: user_score.total_score,
-            new_best_score: user_score.best_single_score,
-            timestamp: user_score.last_submission_time,
+            timestamp: Clock::get()?.unix_timestamp as u64,
         });
+        Ok(())
+    }
+
+    // Close an idle UserScore to reclaim rent into the treasury (owner only).
+    // The final state is emitted first so it survives in the ledger; the user
+    // can start fresh by submitting again.
+    pub fn archive_user_score(ctx: Context<ArchiveUserScore>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let user_score = &ctx.accounts.user_score;
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(
+            leaderboard.archive_retention_seconds >= MIN_ARCHIVE_RETENTION_SECONDS,
+            LeaderboardError::ArchivingDisabled
+        );
+        // Registrants who never submitted have no activity to measure idleness
+        // by, and archiving would forfeit their entry fee
+        require!(
+            user_score.submission_count > 0
+                && now.saturating_sub(user_score.last_submission_time)
+                    > leaderboard.archive_retention_seconds,
+            LeaderboardError::UserStillActive
+        );
 
+        release_user_score(leaderboard, user_score, &mut ctx.accounts.histogram)?;
+        emit!(UserScoreArchived {
+            leaderboard: leaderboard.key(),
+            data: user_score.to_data(now / SECONDS_PER_DAY),
+            timestamp: now,
+        });
+        promote_into_freed_spot(
+            leaderboard,
+            &ctx.accounts.waitlist,
+            &mut ctx.accounts.waitlist_score,
+            &ctx.accounts.waitlist_user,
+        )?;
         Ok(())
     }
 
-    // Get a user's score
-    pub fn get_user_score(ctx: Context<GetUserScore>) -> Result<UserScoreData> {
+    // Close the caller's own UserScore and reclaim its rent. The account is
+    // tied to this leaderboard by its seeds, so only this board's counters
+    // move. Gym members must leave first, waitlisted users wait for promotion
+    // or a refund, and flagged users wait for moderation
+    pub fn close_user_score(ctx: Context<CloseUserScore>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
         let user_score = &ctx.accounts.user_score;
-        Ok(UserScoreData {
-            user: user_score.user,
-            total_score: user_score.total_score,
-            best_single_score: user_score.best_single_score,
-            submission_count: user_score.submission_count,
-            last_submission_time: user_score.last_submission_time,
-            first_submission_time: user_score.first_submission_time,
-        })
+        release_user_score(leaderboard, user_score, &mut ctx.accounts.histogram)?;
+
+        let now = Clock::get()?.unix_timestamp as u64;
+        emit!(UserScoreClosed {
+            leaderboard: leaderboard.key(),
+            data: user_score.to_data(now / SECONDS_PER_DAY),
+            timestamp: now,
+        });
+        promote_into_freed_spot(
+            leaderboard,
+            &ctx.accounts.waitlist,
+            &mut ctx.accounts.waitlist_score,
+            &ctx.accounts.waitlist_user,
+        )?;
+        Ok(())
     }
 
-    // Get leaderboard stats
-    pub fn get_stats(ctx: Context<GetStats>) -> Result<LeaderboardStats> {
+    // Replace the age-grading table (owner only). Used entries (multiplier_bps > 0)
+    // must come first, in strictly ascending min_age order
+    pub fn set_age_grades(
+        ctx: Context<AdminContext>,
+        age_grades: [AgeGrade; AGE_GRADE_TABLE_LEN],
+    ) -> Result<()> {
+        let used = age_grades.iter().take_while(|g| g.multiplier_bps > 0).count();
+        require!(
+            age_grades[used..].iter().all(|g| g.multiplier_bps == 0)
+                && age_grades[..used].windows(2).all(|w| w[0].min_age < w[1].min_age),
+            LeaderboardError::InvalidAgeGrades
+        );
+        ctx.accounts.leaderboard.age_grades = age_grades;
+        Ok(())
+    }
+
+    // Replace the milestone thresholds (owner only). Used entries must come
+    // first in strictly ascending order; all zeros restores DEFAULT_MILESTONES
+    pub fn set_milestones(
+        ctx: Context<AdminContext>,
+        milestones: [u64; MILESTONE_COUNT],
+    ) -> Result<()> {
+        let used = milestones.iter().take_while(|&&m| m > 0).count();
+        require!(
+            milestones[used..].iter().all(|&m| m == 0)
+                && milestones[..used].windows(2).all(|w| w[0] < w[1]),
+            LeaderboardError::InvalidMilestones
+        );
+        ctx.accounts.leaderboard.milestones = milestones;
+        Ok(())
+    }
+
+    // Toggle strict-mode requirements (owner only)
+    pub fn set_strict_flags(ctx: Context<AdminContext>, strict_flags: u32) -> Result<()> {
+        ctx.accounts.leaderboard.set_strict_flags(strict_flags)
+    }
+
+    // Move the competition window (owner only, before the window's first submission)
+    pub fn set_submission_window(
+        ctx: Context<AdminContext>,
+        starts_at: u64,
+        end_time: u64,
+    ) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        require!(leaderboard.total_submissions == 0, LeaderboardError::SubmissionWindowLocked);
+        validate_submission_window(starts_at, end_time)?;
+        leaderboard.starts_at = starts_at;
+        leaderboard.end_time = end_time;
+        Ok(())
+    }
+
+    // Start a new season or week (owner only): total_submissions counts
+    // from zero again, so the window can move, while all-time stats and
+    // every UserScore are kept
+    pub fn reset_submission_window(ctx: Context<AdminContext>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.reset_window();
+
+        emit!(SubmissionWindowReset {
+            leaderboard: leaderboard.key(),
+            all_time_submissions: leaderboard.archived_submissions,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+        Ok(())
+    }
+
+    // Sign up before submissions open, paying the one-time entry fee into the treasury
+    pub fn register_participant(ctx: Context<RegisterParticipant>) -> Result<()> {
         let leaderboard = &ctx.accounts.leaderboard;
-        Ok(LeaderboardStats {
-            exercise_name: leaderboard.exercise_name.clone(),
-            total_participants: leaderboard.total_participants,
-            total_submissions: leaderboard.total_submissions,
-        })
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(!leaderboard.cancelled, LeaderboardError::CompetitionCancelled);
+        require!(
+            now >= leaderboard.registration_opens_at
+                && (leaderboard.starts_at == 0 || now < leaderboard.starts_at),
+            LeaderboardError::RegistrationClosed
+        );
+        require!(!ctx.accounts.user_score.registered, LeaderboardError::AlreadyRegistered);
+        require!(!ctx.accounts.user_score.waitlisted, LeaderboardError::AlreadyWaitlisted);
+
+        let entry_fee = leaderboard.entry_fee_lamports;
+        if entry_fee > 0 {
+            let treasury = ctx
+                .accounts
+                .treasury
+                .as_ref()
+                .ok_or(LeaderboardError::TreasuryRequired)?;
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: treasury.to_account_info(),
+                    },
+                ),
+                entry_fee,
+            )?;
+        }
+
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let user_score = &mut ctx.accounts.user_score;
+        user_score.user = ctx.accounts.user.key();
+        user_score.bump = ctx.bumps.user_score;
+        user_score.entry_fee_paid = entry_fee;
+
+        // A full competition queues the registrant; the entry fee is held
+        // until promotion or refund
+        if leaderboard.is_full() {
+            let waitlist = ctx
+                .accounts
+                .waitlist
+                .as_mut()
+                .ok_or(LeaderboardError::WaitlistRequired)?;
+            return join_waitlist(
+                leaderboard,
+                user_score,
+                waitlist,
+                ctx.bumps.waitlist.unwrap_or_default(),
+                now,
+            );
+        }
+
+        user_score.registered = true;
+        leaderboard.admit_participant()?;
+
+        emit!(ParticipantRegistered {
+            leaderboard: leaderboard.key(),
+            user: user_score.user,
+            entry_fee,
+        });
+        Ok(())
     }
-}
 
-// ========================= ACCOUNTS =========================
+    // Admit the longest-waiting waitlist entry. Anyone may call this once a spot
+    // frees up; the owner may also promote past the cap
+    pub fn promote_from_waitlist(ctx: Context<PromoteFromWaitlist>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        require!(
+            !leaderboard.is_full() || ctx.accounts.caller.key() == leaderboard.owner,
+            LeaderboardError::LeaderboardFull
+        );
 
-#[account]
-pub struct Leaderboard {
-    pub exercise_name: String,      // "pullups"
-    pub total_participants: u64,    // Number of unique users
-    pub total_submissions: u64,     // Total number of submissions
-}
+        admit_from_waitlist(
+            leaderboard,
+            &ctx.accounts.waitlist,
+            &mut ctx.accounts.user_score,
+        )
+    }
 
-#[account]
-pub struct UserScore {
-    pub user: Pubkey,                   // User's wallet address
-    pub total_score: u64,               // Cumulative pullups score
-    pub best_single_score: u64,         // Best single pullups submission
-    pub submission_count: u64,          // Number of submissions
-    pub last_submission_time: u64,      // Unix timestamp
-    pub first_submission_time: u64,     // Unix timestamp
-}
+    // Call off a competition that hasn't started yet (owner only); entry fees
+    // can then be returned with refund_entry
+    pub fn cancel_competition(ctx: Context<AdminContext>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(
+            leaderboard.starts_at != 0 && now < leaderboard.starts_at,
+            LeaderboardError::CompetitionStarted
+        );
+        leaderboard.cancelled = true;
 
-// ========================= CONTEXTS =========================
+        emit!(CompetitionCancelled {
+            leaderboard: leaderboard.key(),
+            timestamp: now,
+        });
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(init, payer = owner, space = 8 + 256)]
-    pub leaderboard: Account<'info, Leaderboard>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    // Return a registrant's entry fee from the treasury after cancellation (owner only)
+    pub fn refund_entry(ctx: Context<RefundEntry>) -> Result<()> {
+        require!(ctx.accounts.leaderboard.cancelled, LeaderboardError::CompetitionNotCancelled);
 
-#[derive(Accounts)]
-pub struct SubmitScore<'info> {
-    #[account(mut)]
-    pub leaderboard: Account<'info, Leaderboard>,
-    #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + 144,
-        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
-        bump
-    )]
-    pub user_score: Account<'info, UserScore>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        let user_score = &mut ctx.accounts.user_score;
+        let amount = user_score.entry_fee_paid;
+        require!(amount > 0, LeaderboardError::NothingToRefund);
 
-#[derive(Accounts)]
-pub struct GetUserScore<'info> {
-    pub user_score: Account<'info, UserScore>,
-}
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+        let user_info = ctx.accounts.user.to_account_info();
+        let rent_floor = Rent::get()?.minimum_balance(treasury_info.data_len());
+        let available = treasury_info.lamports().saturating_sub(rent_floor);
+        require!(amount <= available, LeaderboardError::InsufficientTreasuryBalance);
 
-#[derive(Accounts)]
-pub struct GetStats<'info> {
-    pub leaderboard: Account<'info, Leaderboard>,
-}
+        **treasury_info.try_borrow_mut_lamports()? -= amount;
+        **user_info.try_borrow_mut_lamports()? += amount;
 
-// ========================= EVENTS =========================
+        user_score.registered = false;
+        user_score.waitlisted = false;
+        user_score.entry_fee_paid = 0;
 
-#[event]
-pub struct ScoreSubmitted {
-    pub user: Pubkey,
-    pub score_added: u64,
-    pub new_total_score: u64,
-    pub new_best_score: u64,
-    pub timestamp: u64,
-}
+        emit!(EntryRefunded {
+            leaderboard: ctx.accounts.leaderboard.key(),
+            user: user_score.user,
+            amount,
+        });
+        Ok(())
+    }
 
-// ========================= DATA TYPES =========================
+    // Offer the board to a new owner (owner only); nothing changes until they
+    // accept, so a mistyped key can't take control. Pubkey::default() cancels
+    pub fn transfer_leaderboard_ownership(
+        ctx: Context<AdminContext>,
+        new_owner: Pubkey,
+    ) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.pending_owner = new_owner;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct UserScoreData {
-    pub user: Pubkey,
-    pub total_score: u64,
-    pub best_single_score: u64,
-    pub submission_count: u64,
-    pub last_submission_time: u64,
-    pub first_submission_time: u64,
-}
+        emit!(OwnershipTransferStarted {
+            leaderboard: leaderboard.key(),
+            owner: leaderboard.owner,
+            pending_owner: new_owner,
+        });
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct LeaderboardStats {
-    pub exercise_name: String,
-    pub total_participants: u64,
-    pub total_submissions: u64,
-}
\ No newline at end of file
+    // Second step of transfer_leaderboard_ownership, signed by the new owner
+    pub fn accept_leaderboard_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let previous_owner = leaderboard.owner;
+        leaderboard.owner = ctx.accounts.new_owner.key();
+        leaderboard.pending_owner = Pubkey::default();
+
+        emit!(OwnershipTransferred {
+            leaderboard: leaderboard.key(),
+            previous_owner,
+            new_owner: leaderboard.owner,
+        });
+        Ok(())
+    }
+
+    // Hand administration to a council of owners (owner only). The council PDA
+    // becomes leaderboard.owner, so admin changes then go through proposals;
+    // AdminAction::Invoke reaches every owner-gated instruction. Instructions
+    // that create accounts take a separate payer, since the PDA can't pay
+    pub fn enable_council(
+        ctx: Context<EnableCouncil>,
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        Council::validate_members(&owners, threshold)?;
+
+        let council = &mut ctx.accounts.council;
+        council.leaderboard = ctx.accounts.leaderboard.key();
+        council.owners = owners.clone();
+        council.threshold = threshold;
+        council.proposal_count = 0;
+        council.bump = ctx.bumps.council;
+
+        // A transfer offered before the council existed no longer applies
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.owner = council.key();
+        leaderboard.pending_owner = Pubkey::default();
+
+        emit!(CouncilEnabled {
+            leaderboard: council.leaderboard,
+            council: council.key(),
+            owners,
+            threshold,
+        });
+        Ok(())
+    }
+
+    // Open a proposal; the proposer's approval is recorded immediately
+    pub fn propose_action(ctx: Context<ProposeAction>, action: AdminAction) -> Result<()> {
+        let council = &mut ctx.accounts.council;
+        let proposer = ctx.accounts.proposer.key();
+        require!(council.is_member(&proposer), LeaderboardError::NotCouncilMember);
+        if let AdminAction::Invoke { accounts, data } = &action {
+            require!(
+                accounts.len() <= MAX_PROPOSAL_ACCOUNTS && data.len() <= MAX_PROPOSAL_DATA_LEN,
+                LeaderboardError::InvalidProposal
+            );
+        }
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.council = council.key();
+        proposal.index = council.proposal_count;
+        proposal.proposer = proposer;
+        proposal.action = action;
+        proposal.approvals = vec![proposer];
+        proposal.bump = ctx.bumps.proposal;
+
+        council.proposal_count = council
+            .proposal_count
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+        emit!(ActionProposed {
+            council: proposal.council,
+            proposal_index: proposal.index,
+            proposer,
+        });
+        Ok(())
+    }
+
+    pub fn approve_action(ctx: Context<ApproveAction>) -> Result<()> {
+        let approver = ctx.accounts.approver.key();
+        require!(
+            ctx.accounts.council.is_member(&approver),
+            LeaderboardError::NotCouncilMember
+        );
+
+        let proposal = &mut ctx.accounts.proposal;
+        require!(
+            !proposal.approvals.contains(&approver),
+            LeaderboardError::AlreadyApproved
+        );
+        proposal.approvals.push(approver);
+
+        emit!(ActionApproved {
+            council: proposal.council,
+            proposal_index: proposal.index,
+            approver,
+            approvals: ctx.accounts.council.approval_count(proposal) as u8,
+        });
+        Ok(())
+    }
+
+    // Apply a proposal once enough current members approve; closing the
+    // proposal refunds the proposer and makes replay impossible
+    pub fn execute_action(ctx: Context<ExecuteAction>) -> Result<()> {
+        let council = &mut ctx.accounts.council;
+        require!(
+            council.is_member(&ctx.accounts.executor.key()),
+            LeaderboardError::NotCouncilMember
+        );
+
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            council.approval_count(proposal) >= council.threshold as usize,
+            LeaderboardError::ThresholdNotMet
+        );
+
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        match proposal.action.clone() {
+            AdminAction::UpdateConfig(config) => leaderboard.apply_config(&config)?,
+            AdminAction::SetSubmissionWindow { starts_at, end_time } => {
+                require!(leaderboard.total_submissions == 0, LeaderboardError::SubmissionWindowLocked);
+                validate_submission_window(starts_at, end_time)?;
+                leaderboard.starts_at = starts_at;
+                leaderboard.end_time = end_time;
+            }
+            AdminAction::SetCouncil { owners, threshold } => {
+                Council::validate_members(&owners, threshold)?;
+                council.owners = owners;
+                council.threshold = threshold;
+            }
+            // Voted on by the council, so it applies in one step
+            AdminAction::TransferOwnership(new_owner) => {
+                let previous_owner = leaderboard.owner;
+                leaderboard.owner = new_owner;
+                leaderboard.pending_owner = Pubkey::default();
+                emit!(OwnershipTransferred {
+                    leaderboard: leaderboard.key(),
+                    previous_owner,
+                    new_owner,
+                });
+            }
+            // Any other owner-gated instruction, with the council PDA signing
+            // as leaderboard.owner. remaining_accounts must be the proposal's
+            // accounts in order, followed by this program
+            AdminAction::Invoke { accounts, data } => {
+                let remaining = ctx.remaining_accounts;
+                require!(
+                    remaining.len() == accounts.len() + 1
+                        && remaining.iter().zip(&accounts).all(|(info, a)| *info.key == a.pubkey)
+                        && *remaining[accounts.len()].key == crate::ID,
+                    LeaderboardError::InvalidProposal
+                );
+                let instruction = Instruction {
+                    program_id: crate::ID,
+                    accounts: accounts
+                        .iter()
+                        .map(|a| AccountMeta {
+                            pubkey: a.pubkey,
+                            is_signer: a.is_signer,
+                            is_writable: a.is_writable,
+                        })
+                        .collect(),
+                    data,
+                };
+                let leaderboard_key = leaderboard.key();
+                invoke_signed(
+                    &instruction,
+                    remaining,
+                    &[&[b"council", leaderboard_key.as_ref(), &[council.bump]]],
+                )?;
+                // The inner instruction wrote the leaderboard directly; reload
+                // so this copy doesn't overwrite it on exit
+                leaderboard.reload()?;
+            }
+        }
+
+        emit!(ActionExecuted {
+            council: council.key(),
+            proposal_index: proposal.index,
+            executor: ctx.accounts.executor.key(),
+        });
+        Ok(())
+    }
+
+    // Withdraw collected fees from the treasury (owner only)
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let treasury_info = ctx.accounts.treasury.to_account_info();
+        let recipient_info = ctx.accounts.recipient.to_account_info();
+
+        // Keep the treasury rent-exempt
+        let rent_floor = Rent::get()?.minimum_balance(treasury_info.data_len());
+        let available = treasury_info.lamports().saturating_sub(rent_floor);
+        require!(amount <= available, LeaderboardError::InsufficientTreasuryBalance);
+
+        **treasury_info.try_borrow_mut_lamports()? -= amount;
+        **recipient_info.try_borrow_mut_lamports()? += amount;
+
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        leaderboard.treasury_withdrawn = leaderboard
+            .treasury_withdrawn
+            .checked_add(amount)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+        emit!(TreasuryWithdrawn {
+            leaderboard: leaderboard.key(),
+            recipient: recipient_info.key(),
+            amount,
+            total_withdrawn: leaderboard.treasury_withdrawn,
+        });
+        Ok(())
+    }
+
+    // Overwrite a corrupted user's totals (owner or moderator)
+    pub fn admin_set_user_score(
+        ctx: Context<AdminSetUserScore>,
+        total_score: u64,
+        best_single_score: u64,
+        submission_count: u64,
+    ) -> Result<()> {
+        require_moderation_rights(
+            &ctx.accounts.leaderboard,
+            &ctx.accounts.authority,
+            &ctx.accounts.moderator_marker,
+        )?;
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let user_score = &mut ctx.accounts.user_score;
+
+        let old_total_score = user_score.total_score;
+        let old_best_single_score = user_score.best_single_score;
+        let old_submission_count = user_score.submission_count;
+
+        // Keep the leaderboard submission count and score sum consistent with the change
+        if submission_count >= old_submission_count {
+            leaderboard.total_submissions = leaderboard
+                .total_submissions
+                .checked_add(submission_count - old_submission_count)
+                .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        } else {
+            leaderboard.remove_submissions(old_submission_count - submission_count);
+        }
+        leaderboard.total_score_all_users = if total_score >= old_total_score {
+            leaderboard
+                .total_score_all_users
+                .checked_add(total_score - old_total_score)
+                .ok_or(LeaderboardError::ArithmeticOverflow)?
+        } else {
+            leaderboard
+                .total_score_all_users
+                .saturating_sub(old_total_score - total_score)
+        };
+
+        user_score.total_score = total_score;
+        user_score.best_single_score = best_single_score;
+        user_score.submission_count = submission_count;
+        sync_best_score(
+            leaderboard,
+            user_score,
+            &mut ctx.accounts.histogram,
+            old_best_single_score,
+        )?;
+
+        emit!(AdminScoreAdjusted {
+            user: user_score.user,
+            admin: ctx.accounts.authority.key(),
+            old_total_score,
+            new_total_score: total_score,
+            old_best_single_score,
+            new_best_single_score: best_single_score,
+            old_submission_count,
+            new_submission_count: submission_count,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+        Ok(())
+    }
+
+    // Step 1 of a wallet rotation: the old wallet names its successor
+    pub fn authorize_merge(ctx: Context<UpdateOwnScore>, new_wallet: Pubkey) -> Result<()> {
+        let user_score = &mut ctx.accounts.user_score;
+        require!(new_wallet != user_score.user, LeaderboardError::InvalidMergeTarget);
+        user_score.merge_target = new_wallet;
+
+        emit!(MergeAuthorized {
+            leaderboard: ctx.accounts.leaderboard.key(),
+            old_wallet: user_score.user,
+            new_wallet,
+        });
+        Ok(())
+    }
+
+    // Step 2: the new wallet folds the old history into its own UserScore and
+    // the old PDA is closed to it, all in this one instruction. If the new
+    // wallet had no history it takes over the old participant slot and spot;
+    // otherwise two participants become one and the spare spot goes to the
+    // waitlist head. Waitlisted users and gym members can't merge, as with
+    // close_user_score.
+    pub fn execute_merge(ctx: Context<ExecuteMerge>) -> Result<()> {
+        let leaderboard = &mut ctx.accounts.leaderboard;
+        let old_score = &ctx.accounts.old_score;
+        let new_score = &mut ctx.accounts.new_score;
+        require!(!old_score.frozen && !new_score.frozen, LeaderboardError::AccountFrozen);
+        require!(
+            old_score.open_flags == 0 && new_score.open_flags == 0,
+            LeaderboardError::UnderReview
+        );
+        require!(
+            !old_score.waitlisted && !new_score.waitlisted,
+            LeaderboardError::AlreadyWaitlisted
+        );
+        require!(old_score.gym == Pubkey::default(), LeaderboardError::AlreadyInGym);
+
+        // Same rules as release_user_score: a first submission counts a
+        // participant, registering or scoring holds a spot
+        let old_submitted = old_score.submission_count > 0;
+        let new_submitted = new_score.submission_count > 0;
+        if old_submitted && new_submitted {
+            leaderboard.decrement_participants()?;
+        }
+        let spot_freed = (old_submitted || old_score.registered)
+            && (new_submitted || new_score.registered);
+        if spot_freed {
+            leaderboard.release_participant();
+        }
+
+        // Decay both sides to now so the activity scores add up fairly
+        let now = Clock::get()?.unix_timestamp as u64;
+        let mut old_history = (**old_score).clone();
+        old_history.decay_activity(leaderboard.activity_decay_bps, now);
+        if new_submitted {
+            new_score.decay_activity(leaderboard.activity_decay_bps, now);
+        }
+
+        let (old_best, new_best) = (old_score.best_single_score, new_score.best_single_score);
+        let (old_in_histogram, new_in_histogram) = (old_score.in_histogram, new_score.in_histogram);
+        new_score.user = ctx.accounts.new_wallet.key();
+        new_score.bump = ctx.bumps.new_score;
+        new_score.absorb(&old_history)?;
+
+        if leaderboard.record_holder == old_score.user {
+            leaderboard.record_holder = new_score.user;
+        }
+        if old_in_histogram || new_in_histogram {
+            let histogram = ctx
+                .accounts
+                .histogram
+                .as_mut()
+                .ok_or(LeaderboardError::HistogramRequired)?;
+            match (old_in_histogram, new_in_histogram) {
+                (true, true) => {
+                    histogram.remove_user(old_best);
+                    histogram.move_user(new_best, new_score.best_single_score)?;
+                }
+                (true, false) => histogram.move_user(old_best, new_score.best_single_score)?,
+                _ => histogram.move_user(new_best, new_score.best_single_score)?,
+            }
+            new_score.in_histogram = true;
+        }
+
+        emit!(AccountsMerged {
+            leaderboard: leaderboard.key(),
+            old_wallet: old_score.user,
+            new_wallet: new_score.user,
+            new_total_score: new_score.total_score,
+            new_submission_count: new_score.submission_count,
+        });
+        if spot_freed {
+            promote_into_freed_spot(
+                leaderboard,
+                &ctx.accounts.waitlist,
+                &mut ctx.accounts.waitlist_score,
+                &ctx.accounts.waitlist_user,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Lock the caller's own score so nothing can be submitted for it
+    pub fn freeze_my_score(ctx: Context<UpdateOwnScore>) -> Result<()> {
+        let user_score = &mut ctx.accounts.user_score;
+        user_score.frozen = true;
+        user_score.unfreeze_requested_at = 0;
+
+        emit!(ScoreFrozen {
+            user: user_score.user,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+        Ok(())
+    }
+
+    // Unlock the caller's score. With the delay enforced, the first call starts
+    // the UNFREEZE_DELAY_SECONDS timer and a later call completes the unfreeze.
+    pub fn unfreeze_my_score(ctx: Context<UpdateOwnScore>) -> Result<()> {
+        let enforce_delay = ctx.accounts.leaderboard.enforce_unfreeze_delay;
+        let user_score = &mut ctx.accounts.user_score;
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(user_score.frozen, LeaderboardError::AccountNotFrozen);
+
+        if enforce_delay {
+            if user_score.unfreeze_requested_at == 0 {
+                user_score.unfreeze_requested_at = now;
+                emit!(UnfreezeRequested {
+                    user: user_score.user,
+                    unlocks_at: now + UNFREEZE_DELAY_SECONDS,
+                });
+                return Ok(());
+            }
+            require!(
+                now >= user_score.unfreeze_requested_at + UNFREEZE_DELAY_SECONDS,
+                LeaderboardError::UnfreezeDelayActive
+            );
+        }
+
+        user_score.frozen = false;
+        user_score.unfreeze_requested_at = 0;
+        emit!(ScoreUnfrozen {
+            user: user_score.user,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    // Set the user's bodyweight for normalized rankings (0 clears it)
+    pub fn set_bodyweight(ctx: Context<UpdateOwnScore>, bodyweight_kg: u16) -> Result<()> {
+        require!(bodyweight_kg <= MAX_BODYWEIGHT_KG, LeaderboardError::InvalidBodyweight);
+        ctx.accounts.user_score.bodyweight_kg = bodyweight_kg;
+        Ok(())
+    }
+
+    // Record the user's birth year for age grading; it can only be set once
+    pub fn set_birth_year(ctx: Context<UpdateOwnScore>, birth_year: u16) -> Result<()> {
+        let user_score = &mut ctx.accounts.user_score;
+        require!(user_score.birth_year == 0, LeaderboardError::BirthYearLocked);
+        let current_year = year_of(Clock::get()?.unix_timestamp as u64);
+        require!(
+            birth_year >= MIN_BIRTH_YEAR && birth_year <= current_year,
+            LeaderboardError::InvalidBirthYear
+        );
+        user_score.birth_year = birth_year;
+        Ok(())
+    }
+
+    // Choose the weekdays the user's streak expects a submission on. Missing a
+    // rest day keeps the streak; the gap before the next submission is judged
+    // by the schedule in force at that submission
+    pub fn set_streak_schedule(ctx: Context<UpdateOwnScore>, streak_schedule: u8) -> Result<()> {
+        require!(
+            streak_schedule != 0 && streak_schedule & !ALL_TRAINING_DAYS == 0,
+            LeaderboardError::InvalidStreakSchedule
+        );
+        ctx.accounts.user_score.streak_rest_days = !streak_schedule & ALL_TRAINING_DAYS;
+        Ok(())
+    }
+
+    // Create a gym that users on any leaderboard can affiliate with
+    pub fn create_gym(ctx: Context<CreateGym>, name: String) -> Result<()> {
+        let name = name.trim().to_string();
+        require!(
+            !name.is_empty() && name.len() <= MAX_GYM_NAME_LEN,
+            LeaderboardError::InvalidGymName
+        );
+        let gym = &mut ctx.accounts.gym;
+        gym.name = name.clone();
+        gym.admin = ctx.accounts.admin.key();
+        gym.member_count = 0;
+
+        emit!(GymCreated {
+            gym: gym.key(),
+            admin: gym.admin,
+            name,
+        });
+        Ok(())
+    }
+
+    // Affiliate this leaderboard's UserScore with a gym; later submissions
+    // count towards the gym's GymStats
+    pub fn join_gym(ctx: Context<JoinGym>) -> Result<()> {
+        let user_score = &mut ctx.accounts.user_score;
+        require!(user_score.gym == Pubkey::default(), LeaderboardError::AlreadyInGym);
+        let gym = &mut ctx.accounts.gym;
+        user_score.gym = gym.key();
+        gym.member_count = gym
+            .member_count
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+        emit!(GymJoined {
+            gym: gym.key(),
+            user: user_score.user,
+        });
+        Ok(())
+    }
+
+    pub fn leave_gym(ctx: Context<LeaveGym>) -> Result<()> {
+        let user_score = &mut ctx.accounts.user_score;
+        let gym = &mut ctx.accounts.gym;
+        user_score.gym = Pubkey::default();
+        gym.member_count = gym.member_count.saturating_sub(1);
+
+        emit!(GymLeft {
+            gym: gym.key(),
+            user: user_score.user,
+        });
+        Ok(())
+    }
+
+    // Drop a member's affiliation (gym admin only)
+    pub fn remove_gym_member(ctx: Context<RemoveGymMember>) -> Result<()> {
+        let user_score = &mut ctx.accounts.user_score;
+        let gym = &mut ctx.accounts.gym;
+        user_score.gym = Pubkey::default();
+        gym.member_count = gym.member_count.saturating_sub(1);
+
+        emit!(GymMemberRemoved {
+            gym: gym.key(),
+            user: user_score.user,
+            admin: gym.admin,
+        });
+        Ok(())
+    }
+
+    // Set the user's ISO 3166-1 alpha-2 country code ([0, 0] clears it).
+    // Only later submissions count towards the new region; RegionStats
+    // already credited to the old one are left as they are
+    pub fn set_region(ctx: Context<UpdateOwnScore>, region: [u8; 2]) -> Result<()> {
+        require!(
+            region == [0, 0] || region.iter().all(|c| c.is_ascii_uppercase()),
+            LeaderboardError::InvalidRegion
+        );
+        let user_score = &mut ctx.accounts.user_score;
+        if user_score.region != region {
+            user_score.region = region;
+            user_score.region_joined = false;
+        }
+        Ok(())
+    }
+
+    // Opt in or out of public listings
+    pub fn set_visibility(ctx: Context<UpdateOwnScore>, is_public: bool) -> Result<()> {
+        let user_score = &mut ctx.accounts.user_score;
+        user_score.is_private = !is_public;
+
+        emit!(VisibilityChanged {
+            user: user_score.user,
+            is_public,
+        });
+        Ok(())
+    }
+
+    // Grow a Leaderboard created under an older layout to LEADERBOARD_SPACE
+    // (anyone may pay). New bytes are zeroed like migrate_user_score
+    pub fn migrate_leaderboard(ctx: Context<MigrateLeaderboard>) -> Result<()> {
+        let leaderboard = ctx.accounts.leaderboard.to_account_info();
+        require!(
+            leaderboard.try_borrow_data()?.get(..8) == Some(&Leaderboard::DISCRIMINATOR[..]),
+            LeaderboardError::Unauthorized
+        );
+        if leaderboard.data_len() >= LEADERBOARD_SPACE {
+            return Ok(());
+        }
+
+        let rent_due = Rent::get()?
+            .minimum_balance(LEADERBOARD_SPACE)
+            .saturating_sub(leaderboard.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: leaderboard.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+
+        leaderboard.realloc(LEADERBOARD_SPACE, true)?;
+        Ok(())
+    }
+
+    // Grow a UserScore created under an older layout to USER_SCORE_SPACE.
+    // New bytes are zeroed, so appended fields start at their defaults; the
+    // stored bump is then filled in for contexts that check it
+    pub fn migrate_user_score(ctx: Context<MigrateUserScore>) -> Result<()> {
+        let user_score = ctx.accounts.user_score.to_account_info();
+        if user_score.data_len() < USER_SCORE_SPACE {
+            let rent_due = Rent::get()?
+                .minimum_balance(USER_SCORE_SPACE)
+                .saturating_sub(user_score.lamports());
+            if rent_due > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.user.to_account_info(),
+                            to: user_score.clone(),
+                        },
+                    ),
+                    rent_due,
+                )?;
+            }
+            user_score.realloc(USER_SCORE_SPACE, true)?;
+        }
+
+        let mut migrated: Account<UserScore> = Account::try_from(&user_score)?;
+        migrated.bump = ctx.bumps.user_score;
+        migrated.exit(&crate::ID)
+    }
+
+    // Emit a self-contained snapshot of the caller's history for portability.
+    // Reads only; sessions come from the activity log when one is passed
+    pub fn emit_my_history(ctx: Context<EmitMyHistory>) -> Result<()> {
+        let user_score = &ctx.accounts.user_score;
+        let now = Clock::get()?.unix_timestamp as u64;
+
+        let mut recent_sessions = Vec::new();
+        if let Some(activity_log) = &ctx.accounts.activity_log {
+            let log = activity_log.load()?;
+            let written = log.head.min(ACTIVITY_LOG_LEN as u64);
+            for back in 1..=written {
+                let entry = log.entries[((log.head - back) % ACTIVITY_LOG_LEN as u64) as usize];
+                if entry.user == user_score.user {
+                    recent_sessions.push(SessionEntry {
+                        score: entry.score,
+                        timestamp: entry.timestamp,
+                        submission_index: entry.submission_index,
+                    });
+                }
+            }
+        }
+
+        emit!(UserDataExport {
+            leaderboard: ctx.accounts.leaderboard.key(),
+            data: user_score.to_data(now / SECONDS_PER_DAY),
+            current_streak: user_score.current_streak,
+            longest_streak: user_score.longest_streak,
+            daily_buckets: user_score.daily_buckets,
+            recent_sessions,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    // Get a user's score
+    pub fn get_user_score(ctx: Context<GetUserScore>) -> Result<UserScoreData> {
+        let today = Clock::get()?.unix_timestamp as u64 / SECONDS_PER_DAY;
+        Ok(ctx.accounts.user_score.to_data(today))
+    }
+
+    // Read one page of participant pubkeys in join order
+    pub fn get_participant_page(ctx: Context<GetParticipantPage>, _page_index: u64) -> Result<Vec<Pubkey>> {
+        Ok(ctx.accounts.participant_index.participants.clone())
+    }
+
+    // Compare two users without side effects
+    pub fn compare_users(ctx: Context<CompareUsers>) -> Result<Comparison> {
+        let leaderboard = &ctx.accounts.leaderboard;
+        let now = Clock::get()?.unix_timestamp as u64;
+        Ok(compare_user_scores(
+            &leaderboard.ranked_view(&ctx.accounts.user_score_a, now),
+            &leaderboard.ranked_view(&ctx.accounts.user_score_b, now),
+            leaderboard.scoring_mode,
+        ))
+    }
+
+    // Exact 1-based rank of user_score: one plus the number of other users on
+    // this board with a strictly higher ranking score. The board's UserScore
+    // accounts go in remaining_accounts, and the rank is only exact if the
+    // client passes every one
+    pub fn get_user_rank_exact(ctx: Context<GetUserRankExact>) -> Result<u64> {
+        let leaderboard = ctx.accounts.leaderboard.key();
+        let board = &ctx.accounts.leaderboard;
+        let mode = board.scoring_mode;
+        let now = Clock::get()?.unix_timestamp as u64;
+        let target = &ctx.accounts.user_score;
+        let target_score = mode.ranking_score(&board.ranked_view(target, now));
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut higher = 0u64;
+        for info in ctx.remaining_accounts.iter() {
+            if info.key() == target.key() || seen.contains(info.key) {
+                continue;
+            }
+            require_keys_eq!(*info.owner, crate::ID, LeaderboardError::InvalidRankAccount);
+            let other = UserScore::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            // UserScore doesn't store its leaderboard, so the address proves it
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"user_score", leaderboard.as_ref(), other.user.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(expected, info.key(), LeaderboardError::InvalidRankAccount);
+            seen.push(info.key());
+            if mode.ranking_score(&board.ranked_view(&other, now)) > target_score {
+                higher += 1;
+            }
+        }
+        Ok(higher + 1)
+    }
+
+    // Share of counted users whose best score sits in a lower bucket, in bps
+    pub fn get_percentile(ctx: Context<GetPercentile>, score: u64) -> Result<u16> {
+        Ok(ctx.accounts.histogram.percentile_bps(score))
+    }
+
+    // Get leaderboard stats
+    pub fn get_stats(ctx: Context<GetStats>) -> Result<LeaderboardStats> {
+        Ok(ctx.accounts.leaderboard.stats())
+    }
+
+    // Let clients gate UI on what this deployment supports
+    pub fn get_program_info(_ctx: Context<GetProgramInfo>) -> Result<ProgramInfo> {
+        Ok(ProgramInfo::current())
+    }
+}
+
+// ========================= HELPERS =========================
+
+// Decay `total` by `rate_bps` for every full `period` contained in `elapsed`,
+// i.e. total * (1 - rate_bps / 10_000) ^ (elapsed / period), rounded down.
+// A rate or period of 0 disables decay and returns `total` unchanged.
+pub fn apply_decay(total: u64, rate_bps: u16, period: u64, elapsed: u64) -> u64 {
+    if rate_bps == 0 || period == 0 || total == 0 {
+        return total;
+    }
+
+    // Fixed-point exponentiation by squaring keeps this O(log periods)
+    const SCALE: u128 = 1_000_000_000_000;
+    let denominator = BPS_DENOMINATOR as u128;
+    let mut base = (denominator - (rate_bps as u128).min(denominator)) * SCALE / denominator;
+    let mut factor = SCALE;
+    let mut periods = elapsed / period;
+    while periods > 0 && factor > 0 {
+        if periods & 1 == 1 {
+            factor = factor * base / SCALE;
+        }
+        base = base * base / SCALE;
+        periods >>= 1;
+    }
+
+    (total as u128 * factor / SCALE) as u64
+}
+
+// Shared body of submit_score and submit_score_via_cpi. `calling_program`
+// is the allow-listed caller for CPI submissions and default otherwise.
+// Only submit_score chooses a variant, load and set breakdown; the other
+// paths count as bodyweight Strict with no sets.
+fn process_submission(
+    ctx: Context<SubmitScore>,
+    score: u32,
+    client_nonce: u64,
+    referrer: Pubkey,
+    proof_hash: [u8; 32],
+    calling_program: Pubkey,
+    oracle_attested: bool,
+    variant: PullupVariant,
+    added_weight_kg: u16,
+    sets: Vec<u16>,
+) -> Result<()> {
+    // One outsized submission would distort lifetime stats for good
+    require!(
+        score > 0 && score <= ctx.accounts.leaderboard.max_single_submission(),
+        LeaderboardError::ScoreOutOfRange
+    );
+    require!(
+        added_weight_kg <= MAX_ADDED_WEIGHT_KG
+            && (added_weight_kg == 0 || variant == PullupVariant::Weighted),
+        LeaderboardError::InvalidAddedWeight
+    );
+
+    // Also backfills the bump on accounts created before it was stored, so
+    // later instructions can check it without re-deriving
+    ctx.accounts.user_score.bump = ctx.bumps.user_score;
+
+    // A first-time, unregistered wallet arriving at a full competition is
+    // queued instead of scored, and pays no submission fee
+    if ctx.accounts.user_score.submission_count == 0
+        && !ctx.accounts.user_score.registered
+        && ctx.accounts.leaderboard.is_full()
+    {
+        let now = Clock::get()?.unix_timestamp as u64;
+        let user_score = &mut ctx.accounts.user_score;
+        require!(!user_score.waitlisted, LeaderboardError::AlreadyWaitlisted);
+        user_score.user = ctx.accounts.user.key();
+        let waitlist = ctx
+            .accounts
+            .waitlist
+            .as_mut()
+            .ok_or(LeaderboardError::WaitlistRequired)?;
+        return join_waitlist(
+            &mut ctx.accounts.leaderboard,
+            user_score,
+            waitlist,
+            ctx.bumps.waitlist.unwrap_or_default(),
+            now,
+        );
+    }
+
+    // Collect the submission fee into the treasury before recording anything
+    let fee = ctx.accounts.leaderboard.submission_fee_lamports;
+    if fee > 0 {
+        let treasury = ctx
+            .accounts
+            .treasury
+            .as_ref()
+            .ok_or(LeaderboardError::TreasuryRequired)?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: treasury.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+    }
+
+    let leaderboard = &mut ctx.accounts.leaderboard;
+    let user_score = &mut ctx.accounts.user_score;
+    let user_pubkey = ctx.accounts.user.key();
+    // Events carry this instead of the wallet on boards that mask pubkeys
+    let event_user = masked_user(leaderboard, &user_pubkey);
+
+    // Timed competitions only accept scores between starts_at and end_time
+    let now = Clock::get()?.unix_timestamp as u64;
+    require!(
+        leaderboard.starts_at == 0 || now >= leaderboard.starts_at,
+        LeaderboardError::SubmissionWindowClosed
+    );
+    require!(
+        leaderboard.end_time == 0 || now <= leaderboard.end_time,
+        LeaderboardError::EventEnded
+    );
+
+    require!(!leaderboard.cancelled, LeaderboardError::CompetitionCancelled);
+
+    // Self-locked accounts accept nothing until the user unfreezes
+    require!(!user_score.frozen, LeaderboardError::AccountFrozen);
+
+    // Users with too many unresolved flags wait for moderation
+    require!(
+        leaderboard.max_flags == 0 || user_score.open_flags < leaderboard.max_flags,
+        LeaderboardError::UnderReview
+    );
+
+    // The daily counter belongs to today_day
+    let today = now / SECONDS_PER_DAY;
+    let submissions_today = if user_score.today_day == today {
+        user_score.submissions_today
+    } else {
+        0
+    };
+
+    // An all-zero hash means no proof was attached
+    let has_proof = proof_hash != [0u8; 32];
+
+    validate_submission(
+        leaderboard,
+        user_score,
+        now,
+        submissions_today,
+        has_proof,
+        oracle_attested,
+    )?;
+
+    // An endorsing coach must be a live agent in the registry
+    let coach_agent = match ctx.accounts.coach_agent.as_ref() {
+        Some(agent) => verify_coach_agent(agent)?,
+        None => Pubkey::default(),
+    };
+
+    // Kiosks can resend the same session without a nonce; an identical score
+    // inside the dedupe window is treated as a repeat
+    if leaderboard.dedupe_window > 0 {
+        let window = leaderboard.dedupe_window;
+        require!(
+            !user_score
+                .recent_submissions
+                .iter()
+                .any(|recent| recent.score == score && now.saturating_sub(recent.timestamp) < window),
+            LeaderboardError::DuplicateSubmission
+        );
+    }
+    let slot = user_score.recent_submission_head as usize % RECENT_SUBMISSIONS_LEN;
+    user_score.recent_submissions[slot] = RecentSubmission { score, timestamp: now };
+    user_score.recent_submission_head = ((slot + 1) % RECENT_SUBMISSIONS_LEN) as u8;
+
+    // Reject retried submissions (a nonce of 0 opts out of dedup). Attested
+    // submissions are deduplicated by their NonceTracker instead
+    if client_nonce != 0 && !oracle_attested {
+        user_score.accept_nonce(client_nonce)?;
+    }
+
+    let score_u64 = score as u64;
+    let is_new_user = user_score.submission_count == 0;
+    let submission_index = user_score.submission_count; // 0-based, per user
+
+    // Fade the cumulative score for the periods the user was inactive
+    if !is_new_user {
+        user_score.total_score = apply_decay(
+            user_score.total_score,
+            leaderboard.decay_rate_bps,
+            leaderboard.decay_period,
+            now.saturating_sub(user_score.last_submission_time),
+        );
+    }
+
+    // Bring activity_score up to date before crediting this session
+    if is_new_user {
+        user_score.activity_updated_at = now;
+    } else {
+        user_score.decay_activity(leaderboard.activity_decay_bps, now);
+    }
+    user_score.activity_score = user_score
+        .activity_score
+        .checked_add(score_u64)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+    // Update user score data
+    user_score.user = user_pubkey;
+    user_score.total_score = user_score
+        .total_score
+        .checked_add(score_u64)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    user_score.submission_count = user_score
+        .submission_count
+        .checked_add(1)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    user_score.last_submission_time = now;
+
+    // One submission can cross several thresholds; highest_milestone keeps
+    // a total that decays and regrows from celebrating twice
+    for milestone in leaderboard.milestones() {
+        if milestone > user_score.highest_milestone && milestone <= user_score.total_score {
+            user_score.highest_milestone = milestone;
+            emit!(MilestoneReached {
+                user: event_user,
+                milestone,
+                total_score: user_score.total_score,
+                timestamp: now,
+            });
+        }
+    }
+
+    if is_new_user {
+        // Append to the index page the new participant lands on; private
+        // users are counted but never listed
+        if !user_score.is_private {
+            let participant_index = ctx
+                .accounts
+                .participant_index
+                .as_mut()
+                .ok_or(LeaderboardError::ParticipantIndexRequired)?;
+            let slot = leaderboard.next_participant_slot();
+            require!(
+                participant_index.participants.len() < PARTICIPANT_PAGE_SIZE,
+                LeaderboardError::ParticipantPageFull
+            );
+            participant_index.leaderboard = leaderboard.key();
+            participant_index.page_index = slot / PARTICIPANT_PAGE_SIZE as u64;
+            participant_index.bump = ctx.bumps.participant_index.unwrap_or_default();
+            participant_index.participants.push(user_pubkey);
+            leaderboard.participant_index_len = slot
+                .checked_add(1)
+                .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        }
+
+        user_score.first_submission_time = now;
+        leaderboard.increment_participants()?;
+        // Registered wallets already hold a spot
+        if !user_score.registered {
+            leaderboard.admit_participant()?;
+        }
+    }
+
+    // Referral attribution, only on a user's first-ever submission
+    if referrer != Pubkey::default() {
+        require!(is_new_user, LeaderboardError::ReferralNotAllowed);
+        require!(referrer != user_pubkey, LeaderboardError::SelfReferral);
+        let referrer_score = ctx
+            .accounts
+            .referrer_score
+            .as_mut()
+            .ok_or(LeaderboardError::ReferrerAccountRequired)?;
+        referrer_score.referral_count = referrer_score
+            .referral_count
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        user_score.referrer = referrer;
+
+        emit!(ReferralCredited {
+            referrer: masked_user(leaderboard, &referrer),
+            referee: event_user,
+        });
+    }
+
+    // Mirror the submission into the recent-activity ring buffer when provided
+    if let Some(activity_log) = &ctx.accounts.activity_log {
+        activity_log.load_mut()?.push(ActivityEntry {
+            user: user_pubkey,
+            score: score_u64,
+            timestamp: now,
+            submission_index,
+        });
+    }
+
+    if has_proof {
+        // Each proof may back exactly one submission per leaderboard
+        let leaderboard_key = leaderboard.key();
+        let seeds: &[&[u8]] = &[b"proof", leaderboard_key.as_ref(), proof_hash.as_ref()];
+        let (expected, bump) = Pubkey::find_program_address(seeds, ctx.program_id);
+        let proof_record = ctx
+            .accounts
+            .proof_record
+            .as_ref()
+            .ok_or(LeaderboardError::ProofRecordRequired)?;
+        require_keys_eq!(proof_record.key(), expected, LeaderboardError::ProofRecordRequired);
+        // Used means a ProofRecord was written. Lamports prove nothing, since
+        // anyone can fund the PDA of a public proof hash in advance
+        let proof_record_info = proof_record.to_account_info();
+        require!(
+            proof_record_info.try_borrow_data()?.get(..8) != Some(&ProofRecord::DISCRIMINATOR[..]),
+            LeaderboardError::ProofAlreadyUsed
+        );
+
+        create_pda(
+            &ctx.accounts.user.to_account_info(),
+            &proof_record_info,
+            &ctx.accounts.system_program.to_account_info(),
+            &[b"proof", leaderboard_key.as_ref(), proof_hash.as_ref(), &[bump]],
+            PROOF_RECORD_SPACE,
+            ctx.program_id,
+        )?;
+        ProofRecord {
+            leaderboard: leaderboard_key,
+            user: user_pubkey,
+            proof_hash,
+            timestamp: now,
+            bump,
+        }
+        .try_serialize(&mut &mut proof_record_info.try_borrow_mut_data()?[..])?;
+
+        user_score.last_proof_hash = proof_hash;
+        user_score.proof_count = user_score
+            .proof_count
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    }
+
+    // Update best single score
+    let old_best_single_score = user_score.best_single_score;
+    if score_u64 > user_score.best_single_score {
+        user_score.best_single_score = score_u64;
+        emit!(PersonalRecord {
+            user: event_user,
+            previous_best: old_best_single_score,
+            new_best: score_u64,
+            improvement: score_u64 - old_best_single_score,
+        });
+    }
+    // Per-variant counters sit alongside the combined total_score
+    user_score.record_variant(variant, score_u64)?;
+    // Only an oracle signature vouches for the count; a coach_agent is
+    // named by the submitter and proves nothing on its own
+    let verified = oracle_attested;
+    if verified {
+        user_score.verified_total = user_score
+            .verified_total
+            .checked_add(score_u64)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    }
+    user_score.record_load(score_u64, added_weight_kg)?;
+    if let Some(&best_set) = sets.iter().max() {
+        user_score.best_single_set = user_score.best_single_set.max(best_set);
+        user_score.best_session_volume = user_score.best_session_volume.max(score_u64);
+    }
+
+    // Community-wide totals for the stats page
+    leaderboard.total_score_all_users = leaderboard
+        .total_score_all_users
+        .checked_add(score_u64)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    sync_best_score(leaderboard, user_score, &mut ctx.accounts.histogram, old_best_single_score)?;
+
+    // Best of the current period, reset when a new period starts
+    let period_start = now - now % leaderboard.best_period_length();
+    if user_score.period_start != period_start {
+        user_score.period_start = period_start;
+        user_score.best_this_period = 0;
+    }
+    user_score.best_this_period = user_score.best_this_period.max(score_u64);
+
+    // Bodyweight-normalized total, only for users who set a bodyweight
+    if user_score.bodyweight_kg > 0 {
+        let bodyweight = user_score.bodyweight_kg.max(MIN_NORMALIZED_BODYWEIGHT_KG) as u64;
+        user_score.normalized_total = user_score
+            .normalized_total
+            .checked_add(score_u64 * 1000 / bodyweight)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    }
+
+    // Regional totals for country leaderboards
+    if user_score.region != [0, 0] {
+        let region_stats = ctx
+            .accounts
+            .region_stats
+            .as_mut()
+            .ok_or(LeaderboardError::RegionStatsRequired)?;
+        region_stats.leaderboard = leaderboard.key();
+        region_stats.region = user_score.region;
+        region_stats.bump = ctx.bumps.region_stats.unwrap_or_default();
+        if !user_score.region_joined {
+            region_stats.participants = region_stats
+                .participants
+                .checked_add(1)
+                .ok_or(LeaderboardError::ArithmeticOverflow)?;
+            user_score.region_joined = true;
+        }
+        region_stats.total_score = region_stats
+            .total_score
+            .checked_add(score_u64)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    }
+
+    // Gym totals for the user's current affiliation
+    if user_score.gym != Pubkey::default() {
+        let gym_stats = ctx
+            .accounts
+            .gym_stats
+            .as_mut()
+            .ok_or(LeaderboardError::GymStatsRequired)?;
+        gym_stats.gym = user_score.gym;
+        gym_stats.leaderboard = leaderboard.key();
+        gym_stats.bump = ctx.bumps.gym_stats.unwrap_or_default();
+        gym_stats.submissions = gym_stats
+            .submissions
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        gym_stats.total_score = gym_stats
+            .total_score
+            .checked_add(score_u64)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    }
+
+    // Age-graded total uses the bracket the user is in today; raw totals stay untouched
+    let multiplier_bps = leaderboard.age_multiplier_bps(user_score.birth_year, now);
+    user_score.age_graded_total = user_score
+        .age_graded_total
+        .checked_add(score_u64 * multiplier_bps as u64 / BPS_DENOMINATOR)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+    // Accumulate today's total, resetting on UTC day rollover
+    if user_score.today_day != today {
+        // The streak survives a gap made only of rest days
+        user_score.current_streak =
+            if missed_training_day(user_score.streak_schedule(), user_score.today_day, today) {
+                1
+            } else {
+                user_score.current_streak + 1
+            };
+        user_score.longest_streak = user_score.longest_streak.max(user_score.current_streak);
+        user_score.today_day = today;
+        user_score.today_total = 0;
+        user_score.submissions_today = 0;
+    }
+    user_score.today_total = user_score
+        .today_total
+        .checked_add(score_u64)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    user_score.submissions_today = user_score
+        .submissions_today
+        .checked_add(1)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+    // Rolling daily buckets; a bucket left over from an older day is reused
+    let bucket = &mut user_score.daily_buckets[today as usize % ROLLING_DAYS];
+    if bucket.day != today {
+        *bucket = DailyBucket { day: today, total: 0 };
+    }
+    bucket.total = bucket
+        .total
+        .checked_add(score_u64)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+    if user_score.today_total > user_score.best_daily_total {
+        let previous_best_daily_total = user_score.best_daily_total;
+        user_score.best_daily_total = user_score.today_total;
+        user_score.best_daily_date = today;
+        emit!(DailyBestUpdated {
+            user: event_user,
+            best_daily_total: user_score.best_daily_total,
+            best_daily_date: user_score.best_daily_date,
+            timestamp: now,
+            previous_best_daily_total,
+        });
+    }
+
+    if coach_agent != Pubkey::default() {
+        user_score.coached_submissions = user_score
+            .coached_submissions
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    }
+
+    leaderboard.total_submissions = leaderboard
+        .total_submissions
+        .checked_add(1)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+    // Emit event
+    emit!(ScoreSubmitted {
+        user: event_user,
+        score_added: score_u64,
+        new_total_score: user_score.total_score,
+        new_best_score: user_score.best_single_score,
+        timestamp: user_score.last_submission_time,
+        client_nonce,
+        leaderboard: leaderboard.key(),
+        exercise: leaderboard.exercise_name.clone(),
+        submission_index,
+        proof_hash,
+        calling_program,
+        coach_agent,
+        variant,
+        added_weight_kg,
+        sets,
+        verified,
+    });
+
+    Ok(())
+}
+
+// Identifier written to submission events. Masked boards emit
+// hash(leaderboard || wallet): stable per board, not linkable across
+// boards. Anyone holding a candidate wallet can still test it
+pub fn masked_user(leaderboard: &Account<Leaderboard>, user: &Pubkey) -> Pubkey {
+    if !leaderboard.mask_pubkeys {
+        return *user;
+    }
+    let preimage = [leaderboard.key().as_ref(), user.as_ref()].concat();
+    Pubkey::new_from_array(hash(&preimage).to_bytes())
+}
+
+// Opt-in strict checks live together so a new one only claims a bit in
+// strict_flags and a branch here
+fn validate_submission(
+    leaderboard: &Leaderboard,
+    user_score: &UserScore,
+    now: u64,
+    submissions_today: u32,
+    has_proof: bool,
+    oracle_attested: bool,
+) -> Result<()> {
+    if leaderboard.requires(REQUIRE_ATTESTATION) {
+        require!(oracle_attested, LeaderboardError::InvalidOracleSignature);
+    }
+    if leaderboard.requires(REQUIRE_REGISTRATION) {
+        require!(user_score.registered, LeaderboardError::NotRegistered);
+    }
+    if leaderboard.requires(REQUIRE_PROOF_HASH) {
+        require!(has_proof, LeaderboardError::ProofRequired);
+    }
+    if leaderboard.requires(ENFORCE_RATE_LIMITS) {
+        require!(
+            leaderboard.max_submissions_per_day == 0
+                || submissions_today < leaderboard.max_submissions_per_day,
+            LeaderboardError::DailyLimitReached
+        );
+    }
+    // A first submission has no previous one to wait on
+    if leaderboard.requires(ENFORCE_COOLDOWN) && user_score.submission_count > 0 {
+        require!(
+            now.saturating_sub(user_score.last_submission_time) >= leaderboard.submission_cooldown,
+            LeaderboardError::SubmissionCooldown
+        );
+    }
+    Ok(())
+}
+
+// Create a program-owned PDA the way Anchor's init does. An address that
+// already holds lamports is topped up to rent exemption, then allocated and
+// assigned, so pre-funding it can't block creation
+fn create_pda<'info>(
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    space: usize,
+    owner: &Pubkey,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    if target.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: target.clone(),
+                },
+                &[signer_seeds],
+            ),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+
+    let top_up = rent.saturating_sub(target.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: target.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate {
+                account_to_allocate: target.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign {
+                account_to_assign: target.clone(),
+            },
+            &[signer_seeds],
+        ),
+        owner,
+    )
+}
+
+// A CPI submission must come directly from an allow-listed program whose
+// PDA is the signing user. A wallet user would have to sign the top-level
+// instruction, whereas a PDA signature can only come from invoke_signed.
+//
+// The flow: the leaderboard owner calls allow_cpi_program(caller_id), which
+// creates the ["cpi_program", leaderboard, caller_id] entry. The caller's
+// instruction is then the top-level one in its transaction. It passes its
+// PDA as writable but not as a signer, funds the PDA for the UserScore rent,
+// and signs for it only inside the CPI, e.g. from an Anchor program:
+//
+//     let seeds: &[&[u8]] = &[b"athlete", member.as_ref(), &[athlete_bump]];
+//     solana_pullups_leaderboard::cpi::submit_score_via_cpi(
+//         CpiContext::new_with_signer(
+//             ctx.accounts.leaderboard_program.to_account_info(),
+//             solana_pullups_leaderboard::cpi::accounts::SubmitScore {
+//                 leaderboard: ctx.accounts.leaderboard.to_account_info(),
+//                 user_score: ctx.accounts.athlete_score.to_account_info(),
+//                 user: ctx.accounts.athlete.to_account_info(),
+//                 system_program: ctx.accounts.system_program.to_account_info(),
+//                 cpi_program: Some(ctx.accounts.allow_list_entry.to_account_info()),
+//                 instructions: Some(ctx.accounts.instructions.to_account_info()),
+//                 // Remaining optional accounts as the board requires, else None
+//                 ..
+//             },
+//             &[seeds],
+//         ),
+//         score,
+//         client_nonce,
+//         Pubkey::default(),
+//         [0; 32],
+//     )?;
+fn verify_cpi_caller(ctx: &Context<SubmitScore>) -> Result<Pubkey> {
+    let cpi_program = ctx
+        .accounts
+        .cpi_program
+        .as_ref()
+        .ok_or(LeaderboardError::CpiCallerNotAllowed)?;
+    let instructions = ctx
+        .accounts
+        .instructions
+        .as_ref()
+        .ok_or(LeaderboardError::CpiCallerNotAllowed)?;
+
+    require!(
+        get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT + 1,
+        LeaderboardError::CpiCallerNotAllowed
+    );
+    let top_level = get_instruction_relative(0, &instructions.to_account_info())?;
+    require_keys_eq!(
+        top_level.program_id,
+        cpi_program.program_id,
+        LeaderboardError::CpiCallerNotAllowed
+    );
+    let user = ctx.accounts.user.key();
+    require!(
+        !top_level.accounts.iter().any(|meta| meta.pubkey == user && meta.is_signer),
+        LeaderboardError::CpiCallerNotAllowed
+    );
+    Ok(cpi_program.program_id)
+}
+
+// Bytes the oracle signs: leaderboard || user || score (le) || client_nonce (le)
+pub fn signed_score_message(leaderboard: &Pubkey, user: &Pubkey, score: u32, client_nonce: u64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + 32 + 4 + 8);
+    message.extend_from_slice(leaderboard.as_ref());
+    message.extend_from_slice(user.as_ref());
+    message.extend_from_slice(&score.to_le_bytes());
+    message.extend_from_slice(&client_nonce.to_le_bytes());
+    message
+}
+
+// The Ed25519 program has already checked the signature; confirm it covered
+// our message and was made by the configured oracle, with all data inline
+fn verify_oracle_signature(ctx: &Context<SubmitScore>, message: &[u8]) -> Result<()> {
+    let oracle = ctx.accounts.leaderboard.oracle;
+    require!(oracle != Pubkey::default(), LeaderboardError::InvalidOracleSignature);
+    let instructions = ctx
+        .accounts
+        .instructions
+        .as_ref()
+        .ok_or(LeaderboardError::InvalidOracleSignature)?;
+
+    let ix = get_instruction_relative(-1, &instructions.to_account_info())?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, LeaderboardError::InvalidOracleSignature);
+
+    let data = &ix.data;
+    let read_u16 = |at: usize| -> Result<usize> {
+        let bytes = data
+            .get(at..at + 2)
+            .ok_or(LeaderboardError::InvalidOracleSignature)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+    };
+    // Layout: count, padding, then one Ed25519SignatureOffsets record
+    require!(data.first() == Some(&1), LeaderboardError::InvalidOracleSignature);
+    let public_key_offset = read_u16(6)?;
+    let message_offset = read_u16(10)?;
+    let message_size = read_u16(12)?;
+    for index_at in [4, 8, 14] {
+        require!(read_u16(index_at)? == u16::MAX as usize, LeaderboardError::InvalidOracleSignature);
+    }
+
+    let signer = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(LeaderboardError::InvalidOracleSignature)?;
+    let signed = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(LeaderboardError::InvalidOracleSignature)?;
+    require!(
+        signer == oracle.as_ref() && signed == message,
+        LeaderboardError::InvalidOracleSignature
+    );
+    Ok(())
+}
+
+// Decode an AgentProfile owned by the registry and check it is active
+fn verify_coach_agent(agent: &UncheckedAccount) -> Result<Pubkey> {
+    require_keys_eq!(*agent.owner, AGENT_REGISTRY_PROGRAM_ID, LeaderboardError::InvalidCoachAgent);
+
+    let data = agent.try_borrow_data()?;
+    let discriminator = &hash(b"account:AgentProfile").to_bytes()[..8];
+    require!(
+        data.len() >= 8 && &data[..8] == discriminator,
+        LeaderboardError::InvalidCoachAgent
+    );
+    let profile = AgentProfilePrefix::deserialize(&mut &data[8..])
+        .map_err(|_| LeaderboardError::InvalidCoachAgent)?;
+    require!(profile.is_active, LeaderboardError::CoachAgentInactive);
+    Ok(agent.key())
+}
+
+// Queue a wallet at the tail of the waitlist
+fn join_waitlist(
+    leaderboard: &mut Account<Leaderboard>,
+    user_score: &mut Account<UserScore>,
+    waitlist: &mut Account<Waitlist>,
+    bump: u8,
+    now: u64,
+) -> Result<()> {
+    waitlist.leaderboard = leaderboard.key();
+    waitlist.user = user_score.user;
+    waitlist.position = leaderboard.waitlist_tail;
+    waitlist.joined_at = now;
+    waitlist.bump = bump;
+
+    user_score.waitlisted = true;
+    leaderboard.waitlist_tail = leaderboard
+        .waitlist_tail
+        .checked_add(1)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+    emit!(WaitlistJoined {
+        leaderboard: waitlist.leaderboard,
+        user: waitlist.user,
+        position: waitlist.position,
+    });
+    Ok(())
+}
+
+// Checks and counter updates for a UserScore about to close, shared by
+// close_user_score and archive_user_score. Waitlist entries, gym
+// memberships and open flags would otherwise point at a closed account
+fn release_user_score<'info>(
+    leaderboard: &mut Account<'info, Leaderboard>,
+    user_score: &UserScore,
+    histogram: &mut Option<Account<'info, ScoreHistogram>>,
+) -> Result<()> {
+    require!(!user_score.waitlisted, LeaderboardError::AlreadyWaitlisted);
+    require!(user_score.gym == Pubkey::default(), LeaderboardError::AlreadyInGym);
+    require!(user_score.open_flags == 0, LeaderboardError::UnderReview);
+
+    // Only a first submission counts a participant; registering or scoring
+    // holds a spot
+    if user_score.submission_count > 0 {
+        leaderboard.decrement_participants()?;
+    }
+    if user_score.submission_count > 0 || user_score.registered {
+        leaderboard.release_participant();
+    }
+    leaderboard.release_record(&user_score.user);
+    if user_score.in_histogram {
+        histogram
+            .as_mut()
+            .ok_or(LeaderboardError::HistogramRequired)?
+            .remove_user(user_score.best_single_score);
+    }
+    Ok(())
+}
+
+// Register the waitlist head, shared by promote_from_waitlist and the
+// automatic promotion when a spot is freed
+fn admit_from_waitlist(
+    leaderboard: &mut Account<Leaderboard>,
+    waitlist: &Account<Waitlist>,
+    user_score: &mut Account<UserScore>,
+) -> Result<()> {
+    user_score.waitlisted = false;
+    user_score.registered = true;
+    leaderboard.admit_participant()?;
+    leaderboard.waitlist_head = leaderboard
+        .waitlist_head
+        .checked_add(1)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+    emit!(PromotedFromWaitlist {
+        leaderboard: leaderboard.key(),
+        user: user_score.user,
+        position: waitlist.position,
+    });
+    Ok(())
+}
+
+// Move the waitlist head into a spot freed by closing or archiving a
+// UserScore. Nothing happens while no one waits or the board is still full;
+// otherwise the head's accounts must be passed
+fn promote_into_freed_spot<'info>(
+    leaderboard: &mut Account<'info, Leaderboard>,
+    waitlist: &Option<Account<'info, Waitlist>>,
+    waitlist_score: &mut Option<Account<'info, UserScore>>,
+    waitlist_user: &Option<UncheckedAccount<'info>>,
+) -> Result<()> {
+    if leaderboard.waitlist_head >= leaderboard.waitlist_tail || leaderboard.is_full() {
+        return Ok(());
+    }
+    let (Some(waitlist), Some(waitlist_score), Some(waitlist_user)) =
+        (waitlist.as_ref(), waitlist_score.as_mut(), waitlist_user.as_ref())
+    else {
+        return err!(LeaderboardError::WaitlistRequired);
+    };
+    require_keys_eq!(waitlist.user, waitlist_user.key(), LeaderboardError::Unauthorized);
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"user_score", leaderboard.key().as_ref(), waitlist.user.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(waitlist_score.key(), expected, LeaderboardError::Unauthorized);
+
+    admit_from_waitlist(leaderboard, waitlist, waitlist_score)?;
+    waitlist.close(waitlist_user.to_account_info())
+}
+
+// A breakdown has 1..=MAX_SETS non-empty sets within the per-set cap,
+// summing to the submitted score
+pub fn validate_sets(sets: &[u16], score: u32) -> Result<()> {
+    require!(
+        !sets.is_empty()
+            && sets.len() <= MAX_SETS
+            && sets.iter().all(|&reps| reps > 0 && reps <= MAX_REPS_PER_SET)
+            && sets.iter().map(|&reps| reps as u32).sum::<u32>() == score,
+        LeaderboardError::InvalidSets
+    );
+    Ok(())
+}
+
+// reps * (100 + added_weight_kg * LOAD_PERCENT_PER_KG) / 100, rounded down
+pub fn load_adjusted_score(reps: u64, added_weight_kg: u16) -> u64 {
+    reps * (100 + added_weight_kg as u64 * LOAD_PERCENT_PER_KG) / 100
+}
+
+// Trimmed, lowercase form so "Pullups " and "pullups" name the same board
+pub fn normalize_exercise_name(name: &str) -> Result<String> {
+    let name = name.trim().to_lowercase();
+    require!(!name.is_empty(), LeaderboardError::EmptyExerciseName);
+    require!(name.len() <= MAX_EXERCISE_NAME_LEN, LeaderboardError::InvalidExerciseName);
+    Ok(name)
+}
+
+// UTC calendar year of a unix timestamp. Exact, so an age bracket changes
+// at New Year rather than up to a day either side. Uses the days-to-civil
+// conversion with years counted from March, which puts leap days last
+pub fn year_of(timestamp: u64) -> u16 {
+    let days = timestamp / SECONDS_PER_DAY + 719_468; // Days since 0000-03-01
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // January and February (month index 10 and 11) belong to the next year
+    let january_or_later = (5 * day_of_year + 2) / 153 >= 10;
+    (era * 400 + year_of_era + january_or_later as u64) as u16
+}
+
+// Day 0 of the unix epoch was a Thursday; Monday is weekday 0
+pub fn weekday_of(day: u64) -> u8 {
+    ((day + 3) % 7) as u8
+}
+
+// Whether any scheduled weekday falls strictly between the last active day
+// and today. Gaps of a week or more always contain every weekday
+pub fn missed_training_day(schedule: u8, last_day: u64, today: u64) -> bool {
+    let gap = today.saturating_sub(last_day).saturating_sub(1);
+    if gap >= 7 {
+        return true;
+    }
+    (last_day + 1..today).any(|day| schedule & (1 << weekday_of(day)) != 0)
+}
+
+// 0 leaves either side of the window open; a closed window must end after it starts
+pub fn validate_submission_window(starts_at: u64, end_time: u64) -> Result<()> {
+    require!(
+        starts_at == 0 || end_time == 0 || end_time > starts_at,
+        LeaderboardError::InvalidSubmissionWindow
+    );
+    Ok(())
+}
+
+// Moderation instructions accept the leaderboard owner or any wallet holding
+// a Moderator marker; destructive admin instructions stay owner-only
+pub fn require_moderation_rights(
+    leaderboard: &Account<Leaderboard>,
+    authority: &Signer,
+    moderator_marker: &Option<Account<Moderator>>,
+) -> Result<()> {
+    require!(
+        authority.key() == leaderboard.owner || moderator_marker.is_some(),
+        LeaderboardError::Unauthorized
+    );
+    Ok(())
+}
+
+// Bring the record and the histogram in line with a changed
+// best_single_score. Submissions, moderation and admin corrections all come
+// through here. A holder whose best drops gives the record up, then claims it
+// back below if still above every other best the board knows of. Once a board
+// has a histogram, users who predate it are added the next time they change
+fn sync_best_score<'info>(
+    leaderboard: &mut Leaderboard,
+    user_score: &mut UserScore,
+    histogram: &mut Option<Account<'info, ScoreHistogram>>,
+    old_best: u64,
+) -> Result<()> {
+    if user_score.best_single_score < leaderboard.record_score {
+        leaderboard.release_record(&user_score.user);
+    }
+    if user_score.best_single_score > leaderboard.record_score {
+        leaderboard.record_score = user_score.best_single_score;
+        leaderboard.record_holder = user_score.user;
+    }
+
+    if leaderboard.has_histogram {
+        let histogram = histogram.as_mut().ok_or(LeaderboardError::HistogramRequired)?;
+        if user_score.in_histogram {
+            histogram.move_user(old_best, user_score.best_single_score)?;
+        } else {
+            histogram.add_user(user_score.best_single_score)?;
+            user_score.in_histogram = true;
+        }
+    }
+    Ok(())
+}
+
+// Undo one submission worth `amount` (floored at zero) and return the amount
+// actually removed from the user's total. The session's own score isn't
+// stored, so the best is only capped at what remains of the total
+pub fn reverse_submission(
+    leaderboard: &mut Leaderboard,
+    user_score: &mut UserScore,
+    amount: u64,
+) -> Result<u64> {
+    let removed = amount.min(user_score.total_score);
+    user_score.total_score -= removed;
+    user_score.best_single_score = user_score.best_single_score.min(user_score.total_score);
+    user_score.submission_count = user_score
+        .submission_count
+        .checked_sub(1)
+        .ok_or(LeaderboardError::ArithmeticOverflow)?;
+    leaderboard.remove_submissions(1);
+    leaderboard.total_score_all_users = leaderboard.total_score_all_users.saturating_sub(removed);
+    Ok(removed)
+}
+
+// Rank two users by the leaderboard's ranking score, breaking ties by
+// best_single_score (Cumulative) or total_score (other modes) and then by
+// the earlier first_submission_time
+pub fn compare_user_scores(a: &UserScore, b: &UserScore, mode: ScoringMode) -> Comparison {
+    let key = |s: &UserScore| {
+        let ranking = mode.ranking_score(s);
+        let secondary = match mode {
+            ScoringMode::Cumulative => s.best_single_score,
+            ScoringMode::Best
+            | ScoringMode::LongestStreak
+            | ScoringMode::AgeGraded
+            | ScoringMode::StrictOnly
+            | ScoringMode::LoadAdjusted
+            | ScoringMode::VerifiedOnly
+            | ScoringMode::Normalized
+            | ScoringMode::Activity => s.total_score,
+        };
+        (ranking, secondary, std::cmp::Reverse(s.first_submission_time))
+    };
+    let a_leads = key(a).cmp(&key(b));
+
+    let (leader, trailer) = match a_leads {
+        std::cmp::Ordering::Less => (b, a),
+        _ => (a, b),
+    };
+    Comparison {
+        leader: leader.user,
+        total_score_delta: mode.ranking_score(leader) - mode.ranking_score(trailer),
+        tied: a_leads == std::cmp::Ordering::Equal,
+    }
+}
+
+// ========================= ACCOUNTS =========================
+
+#[account]
+#[derive(InitSpace)]
+pub struct Leaderboard {
+    #[max_len(MAX_EXERCISE_NAME_LEN)]
+    pub exercise_name: String,      // "pullups"
+    pub total_participants: u64,    // Number of unique users
+    pub total_submissions: u64,     // Submissions in the current window
+    pub owner: Pubkey,              // Leaderboard administrator
+    pub submission_fee_lamports: u64, // Fee charged per submission (0 = free)
+    pub decay_rate_bps: u16,        // Score decay per period in bps (0 = no decay)
+    pub decay_period: u64,          // Seconds per decay period
+    pub lock_name_after_first_submission: bool, // Freeze exercise_name once scores exist; otherwise the owner may rename
+    pub require_proof: bool,        // Reject submissions without a proof hash
+    pub max_flags: u8,              // Open flags that block submissions (0 = never)
+    pub activity_decay_bps: u16,    // Weekly activity_score decay in bps (0 = none)
+    pub end_time: u64,              // Submissions close after this time (0 = open-ended)
+    pub enforce_unfreeze_delay: bool, // Unfreezing waits UNFREEZE_DELAY_SECONDS
+    pub oracle: Pubkey,             // Trusted scorer for revocations (default = none)
+    pub scoring_mode: ScoringMode,  // Which UserScore field ranks users
+    pub archive_retention_seconds: u64, // Idle time before archiving (0 = never)
+    pub starts_at: u64,             // Submissions open at this time (0 = immediately)
+    pub registration_opens_at: u64, // register_participant opens at this time
+    pub entry_fee_lamports: u64,    // One-time fee charged at registration (0 = free)
+    pub registered_only: bool,      // Only registered wallets may submit
+    pub cancelled: bool,            // Competition called off; entry fees are refundable
+    pub is_initialized: bool,       // Set by initialize and clone_leaderboard
+    pub max_participants: u32,      // Spots before new wallets are waitlisted (0 = unlimited)
+    pub admitted_participants: u64, // Spots currently held (registered or scoring)
+    pub waitlist_head: u64,         // Position of the next waitlist entry to promote
+    pub waitlist_tail: u64,         // Position the next waitlisted wallet receives
+    pub best_period_seconds: u64,   // best_this_period window length (0 = one week)
+    pub has_histogram: bool,        // Submissions must update the ScoreHistogram
+    pub max_submissions_per_day: u32, // Per-user daily cap (0 = unlimited)
+    pub strict_flags: u32,          // Requirement bits (REQUIRE_*, ENFORCE_*), see requires()
+    pub age_grades: [AgeGrade; AGE_GRADE_TABLE_LEN], // Brackets for ScoringMode::AgeGraded
+    pub total_score_all_users: u64, // Sum of every accepted score
+    pub record_holder: Pubkey,      // Wallet with the best single submission
+    pub record_score: u64,          // That submission's score
+    pub treasury_withdrawn: u64,    // Lamports the owner has withdrawn over the board's life
+    pub dedupe_window: u64,         // Seconds an identical score counts as a repeat (0 = off)
+    pub decimals: u8,               // Scores are fixed-point with this many decimal places
+    pub max_single_submission: u32, // Per-submission score cap (0 = DEFAULT_MAX_SINGLE_SUBMISSION)
+    pub milestones: [u64; MILESTONE_COUNT], // Ascending thresholds, zero-padded (all 0 = defaults)
+    pub mask_pubkeys: bool,         // Submission events carry masked_user ids (false = wallets)
+    pub bump: u8,                   // PDA bump for the official board (0 for clones)
+    pub submission_cooldown: u64,   // Seconds between a user's submissions under ENFORCE_COOLDOWN
+    pub pending_owner: Pubkey,      // Offered by transfer_leaderboard_ownership (default = none)
+    pub archived_submissions: u64,  // Submissions from windows before the last reset
+    pub participant_index_len: u64, // Listing slots handed out; never decreases
+}
+
+impl Leaderboard {
+    // Validate and copy the operator-tunable settings onto the leaderboard
+    pub fn apply_config(&mut self, config: &LeaderboardConfig) -> Result<()> {
+        require!(
+            config.decay_rate_bps as u64 <= BPS_DENOMINATOR,
+            LeaderboardError::InvalidDecayConfig
+        );
+
+        self.submission_fee_lamports = config.submission_fee_lamports;
+        self.decay_rate_bps = config.decay_rate_bps;
+        self.decay_period = config.decay_period;
+        self.lock_name_after_first_submission = config.lock_name_after_first_submission;
+        self.require_proof = config.require_proof;
+        self.max_flags = config.max_flags;
+        require!(
+            config.activity_decay_bps as u64 <= BPS_DENOMINATOR,
+            LeaderboardError::InvalidDecayConfig
+        );
+        self.activity_decay_bps = config.activity_decay_bps;
+        validate_submission_window(config.starts_at, config.end_time)?;
+        self.starts_at = config.starts_at;
+        self.end_time = config.end_time;
+        self.enforce_unfreeze_delay = config.enforce_unfreeze_delay;
+        self.oracle = config.oracle;
+        // Rankings already built under one mode can't be reinterpreted mid-competition
+        require!(
+            self.all_time_submissions() == 0 || config.scoring_mode == self.scoring_mode,
+            LeaderboardError::ScoringModeLocked
+        );
+        self.scoring_mode = config.scoring_mode;
+        require!(
+            config.archive_retention_seconds == 0
+                || config.archive_retention_seconds >= MIN_ARCHIVE_RETENTION_SECONDS,
+            LeaderboardError::RetentionTooShort
+        );
+        self.archive_retention_seconds = config.archive_retention_seconds;
+        self.registration_opens_at = config.registration_opens_at;
+        self.entry_fee_lamports = config.entry_fee_lamports;
+        self.registered_only = config.registered_only;
+        self.max_participants = config.max_participants;
+        self.best_period_seconds = config.best_period_seconds;
+        self.max_submissions_per_day = config.max_submissions_per_day;
+        self.dedupe_window = config.dedupe_window;
+        require!(config.decimals <= MAX_SCORE_DECIMALS, LeaderboardError::InvalidDecimals);
+        // Changing the scale would silently rescale every stored score
+        require!(
+            self.all_time_submissions() == 0 || config.decimals == self.decimals,
+            LeaderboardError::DecimalsLocked
+        );
+        self.decimals = config.decimals;
+        self.max_single_submission = config.max_single_submission;
+        self.mask_pubkeys = config.mask_pubkeys;
+        self.submission_cooldown = config.submission_cooldown;
+
+        // The individual settings imply their strict bits
+        let mut flags = config.strict_flags;
+        if config.require_proof {
+            flags |= REQUIRE_PROOF_HASH;
+        }
+        if config.registered_only {
+            flags |= REQUIRE_REGISTRATION;
+        }
+        if config.max_submissions_per_day > 0 {
+            flags |= ENFORCE_RATE_LIMITS;
+        }
+        if config.submission_cooldown > 0 {
+            flags |= ENFORCE_COOLDOWN;
+        }
+        self.set_strict_flags(flags)
+    }
+
+    // Inverse of apply_config, used to copy settings between boards
+    pub fn config(&self) -> LeaderboardConfig {
+        LeaderboardConfig {
+            submission_fee_lamports: self.submission_fee_lamports,
+            decay_rate_bps: self.decay_rate_bps,
+            decay_period: self.decay_period,
+            lock_name_after_first_submission: self.lock_name_after_first_submission,
+            require_proof: self.require_proof,
+            max_flags: self.max_flags,
+            activity_decay_bps: self.activity_decay_bps,
+            end_time: self.end_time,
+            enforce_unfreeze_delay: self.enforce_unfreeze_delay,
+            oracle: self.oracle,
+            scoring_mode: self.scoring_mode,
+            archive_retention_seconds: self.archive_retention_seconds,
+            starts_at: self.starts_at,
+            registration_opens_at: self.registration_opens_at,
+            entry_fee_lamports: self.entry_fee_lamports,
+            registered_only: self.registered_only,
+            max_participants: self.max_participants,
+            best_period_seconds: self.best_period_seconds,
+            max_submissions_per_day: self.max_submissions_per_day,
+            strict_flags: self.strict_flags,
+            dedupe_window: self.dedupe_window,
+            decimals: self.decimals,
+            max_single_submission: self.max_single_submission,
+            mask_pubkeys: self.mask_pubkeys,
+            submission_cooldown: self.submission_cooldown,
+        }
+    }
+
+    // Replace the strict bitfield, keeping the older booleans in step
+    pub fn set_strict_flags(&mut self, flags: u32) -> Result<()> {
+        require!(flags & !SUPPORTED_STRICT_FLAGS == 0, LeaderboardError::UnsupportedStrictFlag);
+        self.strict_flags = flags;
+        self.require_proof = flags & REQUIRE_PROOF_HASH != 0;
+        self.registered_only = flags & REQUIRE_REGISTRATION != 0;
+        Ok(())
+    }
+
+    // Multiplier for the highest bracket the user's age reaches; users without
+    // a birth year, or younger than every bracket, compete at 1.0
+    pub fn age_multiplier_bps(&self, birth_year: u16, now: u64) -> u16 {
+        if birth_year == 0 {
+            return BPS_DENOMINATOR as u16;
+        }
+        let age = year_of(now).saturating_sub(birth_year);
+        self.age_grades
+            .iter()
+            .take_while(|g| g.multiplier_bps > 0)
+            .filter(|g| g.min_age as u16 <= age)
+            .last()
+            .map_or(BPS_DENOMINATOR as u16, |g| g.multiplier_bps)
+    }
+
+    // Boards created before strict_flags existed only carry the booleans
+    pub fn requires(&self, flag: u32) -> bool {
+        self.strict_flags & flag != 0
+            || (flag == REQUIRE_PROOF_HASH && self.require_proof)
+            || (flag == REQUIRE_REGISTRATION && self.registered_only)
+    }
+
+    // Length of the best_this_period window; unset boards use weeks
+    pub fn best_period_length(&self) -> u64 {
+        if self.best_period_seconds == 0 {
+            SECONDS_PER_WEEK
+        } else {
+            self.best_period_seconds
+        }
+    }
+
+    // Largest score one submission may carry; unset boards use the default
+    pub fn max_single_submission(&self) -> u32 {
+        if self.max_single_submission == 0 {
+            DEFAULT_MAX_SINGLE_SUBMISSION
+        } else {
+            self.max_single_submission
+        }
+    }
+
+    // Thresholds for MilestoneReached; unset boards use the defaults
+    pub fn milestones(&self) -> [u64; MILESTONE_COUNT] {
+        if self.milestones == [0; MILESTONE_COUNT] {
+            DEFAULT_MILESTONES
+        } else {
+            self.milestones
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.max_participants != 0 && self.admitted_participants >= self.max_participants as u64
+    }
+
+    pub fn admit_participant(&mut self) -> Result<()> {
+        self.admitted_participants = self
+            .admitted_participants
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    // activity_score only decays when its account is written, so Activity
+    // boards rank a copy decayed to now
+    pub fn ranked_view(&self, user_score: &UserScore, now: u64) -> UserScore {
+        let mut ranked = user_score.clone();
+        if self.scoring_mode == ScoringMode::Activity {
+            ranked.decay_activity(self.activity_decay_bps, now);
+        }
+        ranked
+    }
+
+    // A record can't point at a UserScore that is going away; it is cleared
+    // and the next submission sets a new one
+    pub fn release_record(&mut self, user: &Pubkey) {
+        if self.record_holder == *user {
+            self.record_holder = Pubkey::default();
+            self.record_score = 0;
+        }
+    }
+
+    // Saturating: boards that predate the cap never counted admissions
+    pub fn release_participant(&mut self) {
+        self.admitted_participants = self.admitted_participants.saturating_sub(1);
+    }
+
+    // Listing slot for the next public participant. Boards that predate
+    // participant_index_len paged by total_participants, so they continue on
+    // the first page past those
+    pub fn next_participant_slot(&self) -> u64 {
+        if self.participant_index_len > 0 {
+            return self.participant_index_len;
+        }
+        let page = PARTICIPANT_PAGE_SIZE as u64;
+        self.total_participants.div_ceil(page) * page
+    }
+
+    // Stored as an offset so boards that predate resets report their window
+    // count, which is then their full history
+    pub fn all_time_submissions(&self) -> u64 {
+        self.archived_submissions.saturating_add(self.total_submissions)
+    }
+
+    pub fn stats(&self) -> LeaderboardStats {
+        LeaderboardStats {
+            exercise_name: self.exercise_name.clone(),
+            total_participants: self.total_participants,
+            total_submissions: self.total_submissions,
+            all_time_submissions: self.all_time_submissions(),
+            total_score_all_users: self.total_score_all_users,
+            record_holder: self.record_holder,
+            record_score: self.record_score,
+            decimals: self.decimals,
+            cancelled: self.cancelled,
+            end_time: self.end_time,
+        }
+    }
+
+    pub fn reset_window(&mut self) {
+        self.archived_submissions = self.all_time_submissions();
+        self.total_submissions = 0;
+    }
+
+    // Take back submissions that were reversed or corrected. Which window a
+    // submission fell in isn't stored, so the current window is emptied first
+    // and anything left comes out of the archived count
+    pub fn remove_submissions(&mut self, count: u64) {
+        let from_window = count.min(self.total_submissions);
+        self.total_submissions -= from_window;
+        self.archived_submissions = self.archived_submissions.saturating_sub(count - from_window);
+    }
+
+    // All participant count changes go through these checked helpers
+    pub fn increment_participants(&mut self) -> Result<()> {
+        self.total_participants = self
+            .total_participants
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    pub fn decrement_participants(&mut self) -> Result<()> {
+        self.total_participants = self
+            .total_participants
+            .checked_sub(1)
+            .ok_or(LeaderboardError::ParticipantUnderflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Treasury {
+    pub leaderboard: Pubkey,        // Leaderboard this treasury collects for
+    pub bump: u8,                   // PDA bump
+}
+
+// Paged list of participants so clients can enumerate a leaderboard without
+// getProgramAccounts. Page N lives at [b"participant_index", leaderboard,
+// N.to_le_bytes()] and holds listing slots N * PARTICIPANT_PAGE_SIZE up to
+// (N + 1) * PARTICIPANT_PAGE_SIZE - 1. Slots come from the never-decreasing
+// Leaderboard.participant_index_len, so clients walk pages 0..=
+// (participant_index_len - 1) / PARTICIPANT_PAGE_SIZE and only the last page
+// can be partially filled. Boards that predate the counter may skip pages
+// before their first counted one. Entries stay when a UserScore closes.
+#[account]
+#[derive(InitSpace)]
+pub struct ParticipantIndex {
+    pub leaderboard: Pubkey,        // Leaderboard being indexed
+    pub page_index: u64,            // Page number in the seeds
+    #[max_len(PARTICIPANT_PAGE_SIZE)]
+    pub participants: Vec<Pubkey>,  // Up to PARTICIPANT_PAGE_SIZE pubkeys
+    pub bump: u8,                   // PDA bump
+}
+
+// Ring buffer of the last ACTIVITY_LOG_LEN submissions. `head` counts every
+// entry ever written, so the newest entry is at (head - 1) % ACTIVITY_LOG_LEN
+// and, once head >= ACTIVITY_LOG_LEN, the oldest is at head % ACTIVITY_LOG_LEN.
+// Clients read min(head, ACTIVITY_LOG_LEN) entries walking back from newest.
+#[account(zero_copy)]
+pub struct ActivityLog {
+    pub leaderboard: Pubkey,        // Leaderboard being logged
+    pub head: u64,                  // Total entries written
+    pub entries: [ActivityEntry; ACTIVITY_LOG_LEN],
+}
+
+impl ActivityLog {
+    pub fn push(&mut self, entry: ActivityEntry) {
+        let slot = (self.head % ACTIVITY_LOG_LEN as u64) as usize;
+        self.entries[slot] = entry;
+        self.head += 1;
+    }
+}
+
+#[zero_copy]
+pub struct ActivityEntry {
+    pub user: Pubkey,
+    pub score: u64,
+    pub timestamp: u64,
+    pub submission_index: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CpiProgram {
+    pub leaderboard: Pubkey,        // Leaderboard the permission applies to
+    pub program_id: Pubkey,         // Program allowed to submit for its PDAs
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Moderator {
+    pub leaderboard: Pubkey,        // Leaderboard the role applies to
+    pub moderator: Pubkey,          // Wallet holding the role
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Flag {
+    pub leaderboard: Pubkey,        // Leaderboard the submission belongs to
+    pub user: Pubkey,               // Flagged user
+    pub submission_index: u64,      // Per-user submission index (see ScoreSubmitted)
+    pub reason: u8,                 // Client-defined reason code
+    pub moderator: Pubkey,          // Who raised the flag
+    pub created_at: u64,            // Unix timestamp
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProofRecord {
+    pub leaderboard: Pubkey,        // Leaderboard the proof was used on
+    pub user: Pubkey,               // Who submitted it
+    pub proof_hash: [u8; 32],       // The proof itself
+    pub timestamp: u64,             // When it was used
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ScoreHistogram {
+    pub leaderboard: Pubkey,        // Leaderboard the distribution describes
+    pub lower_bounds: [u64; HISTOGRAM_BUCKETS], // Ascending bucket floors, first is 0
+    pub counts: [u64; HISTOGRAM_BUCKETS],       // Users whose best falls in each bucket
+    pub total: u64,                 // Users counted across all buckets
+    pub bump: u8,                   // PDA bump
+}
+
+impl ScoreHistogram {
+    pub fn bucket_for(&self, score: u64) -> usize {
+        self.lower_bounds.partition_point(|&lower| lower <= score) - 1
+    }
+
+    pub fn add_user(&mut self, best: u64) -> Result<()> {
+        let bucket = self.bucket_for(best);
+        self.counts[bucket] = self.counts[bucket]
+            .checked_add(1)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.total = self.total.checked_add(1).ok_or(LeaderboardError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    // Saturating, like move_user, so a drifted count can't block a close
+    pub fn remove_user(&mut self, best: u64) {
+        let bucket = self.bucket_for(best);
+        self.counts[bucket] = self.counts[bucket].saturating_sub(1);
+        self.total = self.total.saturating_sub(1);
+    }
+
+    pub fn move_user(&mut self, old_best: u64, new_best: u64) -> Result<()> {
+        let (from, to) = (self.bucket_for(old_best), self.bucket_for(new_best));
+        if from != to {
+            self.counts[from] = self.counts[from].saturating_sub(1);
+            self.counts[to] = self.counts[to]
+                .checked_add(1)
+                .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        }
+        Ok(())
+    }
+
+    // Bucket resolution: users sharing the score's bucket count as not below it
+    pub fn percentile_bps(&self, score: u64) -> u16 {
+        if self.total == 0 {
+            return 0;
+        }
+        let below: u64 = self.counts[..self.bucket_for(score)].iter().sum();
+        (below * BPS_DENOMINATOR / self.total) as u16
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Gym {
+    #[max_len(MAX_GYM_NAME_LEN)]
+    pub name: String,               // Display name
+    pub admin: Pubkey,              // May remove members
+    pub member_count: u64,          // Affiliations across all leaderboards
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct GymStats {
+    pub gym: Pubkey,                // Gym the totals belong to
+    pub leaderboard: Pubkey,        // Leaderboard they were earned on
+    pub total_score: u64,           // Scores submitted by affiliated members
+    pub submissions: u64,           // Submissions by affiliated members
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RegionStats {
+    pub leaderboard: Pubkey,        // Leaderboard the totals belong to
+    pub region: [u8; 2],            // ISO country code
+    pub participants: u64,          // Users who have submitted while in this region
+    pub total_score: u64,           // Scores submitted while in this region
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct NonceTracker {
+    pub leaderboard: Pubkey,        // Leaderboard the nonces were used on
+    pub user: Pubkey,               // Attested user
+    pub window_start: u64,          // Oldest nonce still tracked
+    pub bitmap: [u64; 4],           // Bit i marks window_start + i as used
+    pub bump: u8,                   // PDA bump
+}
+
+impl NonceTracker {
+    // Mark a nonce as used. Nonces past the window slide it forward so the
+    // new nonce is its last bit; anything older than the window is rejected
+    pub fn mark(&mut self, nonce: u64) -> Result<()> {
+        require!(nonce >= self.window_start, LeaderboardError::NonceReplayed);
+        let offset = nonce - self.window_start;
+        if offset >= NONCE_WINDOW_BITS {
+            let new_start = nonce - (NONCE_WINDOW_BITS - 1);
+            self.shift_window(new_start - self.window_start);
+            self.window_start = new_start;
+        }
+
+        let bit = nonce - self.window_start;
+        let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+        require!(self.bitmap[word] & mask == 0, LeaderboardError::NonceReplayed);
+        self.bitmap[word] |= mask;
+        Ok(())
+    }
+
+    // Drop the oldest `by` bits, moving the rest down towards bit 0
+    fn shift_window(&mut self, by: u64) {
+        if by >= NONCE_WINDOW_BITS {
+            self.bitmap = [0; 4];
+            return;
+        }
+        let (words, bits) = ((by / 64) as usize, (by % 64) as u32);
+        let old = self.bitmap;
+        let word_at = |i: usize| old.get(i).copied().unwrap_or(0);
+        for i in 0..4 {
+            let low = word_at(i + words);
+            let high = word_at(i + words + 1);
+            self.bitmap[i] = if bits == 0 {
+                low
+            } else {
+                (low >> bits) | (high << (64 - bits))
+            };
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Waitlist {
+    pub leaderboard: Pubkey,        // Leaderboard being queued for
+    pub user: Pubkey,               // Waiting wallet
+    pub position: u64,              // Sequential; lowest is promoted first
+    pub joined_at: u64,             // Unix timestamp
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Council {
+    pub leaderboard: Pubkey,        // Leaderboard this council administers
+    #[max_len(MAX_COUNCIL_OWNERS)]
+    pub owners: Vec<Pubkey>,        // Members who may propose and approve
+    pub threshold: u8,              // Approvals needed to execute
+    pub proposal_count: u64,        // Next proposal index
+    pub bump: u8,                   // PDA bump
+}
+
+impl Council {
+    pub fn validate_members(owners: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !owners.is_empty() && owners.len() <= MAX_COUNCIL_OWNERS,
+            LeaderboardError::InvalidCouncil
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= owners.len(),
+            LeaderboardError::InvalidCouncil
+        );
+        for (i, owner) in owners.iter().enumerate() {
+            require!(!owners[i + 1..].contains(owner), LeaderboardError::InvalidCouncil);
+        }
+        Ok(())
+    }
+
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        self.owners.contains(key)
+    }
+
+    // Only approvals from current members count, so removed owners' votes lapse
+    pub fn approval_count(&self, proposal: &Proposal) -> usize {
+        proposal.approvals.iter().filter(|a| self.is_member(a)).count()
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub council: Pubkey,            // Council the proposal belongs to
+    pub index: u64,                 // Sequential per council
+    pub proposer: Pubkey,           // Refunded when the proposal executes
+    pub action: AdminAction,        // Change to apply on execution
+    #[max_len(MAX_COUNCIL_OWNERS)]
+    pub approvals: Vec<Pubkey>,     // Members who approved
+    pub bump: u8,                   // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserScore {
+    pub user: Pubkey,                   // User's wallet address
+    pub total_score: u64,               // Cumulative pullups score
+    pub best_single_score: u64,         // Best single pullups submission
+    pub submission_count: u64,          // Number of submissions
+    pub last_submission_time: u64,      // Unix timestamp
+    pub first_submission_time: u64,     // Unix timestamp
+    pub recent_nonces: [u64; RECENT_NONCES_LEN], // Last client nonces accepted (0 = empty slot)
+    pub recent_nonce_head: u8,          // Next recent_nonces slot to overwrite
+    pub last_nonce: u64,                // Highest client nonce accepted
+    pub today_day: u64,                 // UTC day number of today_total
+    pub today_total: u64,               // Running total for today_day
+    pub best_daily_total: u64,          // Best single-day total
+    pub best_daily_date: u64,           // UTC day number of best_daily_total
+    pub bodyweight_kg: u16,             // Self-reported bodyweight (0 = unset)
+    pub normalized_total: u64,          // Sum of reps * 1000 / bodyweight
+    pub referrer: Pubkey,               // Who referred this user (default = none)
+    pub referral_count: u64,            // Users this wallet has referred
+    pub daily_buckets: [DailyBucket; ROLLING_DAYS], // Per-day totals indexed by day % 7
+    pub last_proof_hash: [u8; 32],      // Most recent proof (e.g. IPFS CID digest)
+    pub proof_count: u64,               // Submissions that carried a proof
+    pub open_flags: u8,                 // Unresolved moderator flags
+    pub activity_score: u64,            // Session score with weekly decay applied
+    pub activity_updated_at: u64,       // Start of the first week not yet decayed
+    pub frozen: bool,                   // Self-lock: submissions are rejected
+    pub unfreeze_requested_at: u64,     // Pending unfreeze start (0 = none)
+    pub merge_target: Pubkey,           // Wallet allowed to absorb this account
+    pub registered: bool,               // Signed up through register_participant
+    pub entry_fee_paid: u64,            // Entry fee held for a possible refund
+    pub waitlisted: bool,               // Holds a Waitlist entry awaiting promotion
+    pub best_this_period: u64,          // Best single score since period_start
+    pub period_start: u64,              // Start of the period best_this_period covers
+    pub is_private: bool,               // Hidden from public listings; stored inverted so zeroed accounts stay public
+    pub in_histogram: bool,             // best_single_score is counted in the ScoreHistogram
+    pub current_streak: u64,            // Active days since the last missed scheduled day, ending today_day
+    pub longest_streak: u64,            // Best current_streak reached
+    pub submissions_today: u32,         // Submissions made on today_day
+    pub birth_year: u16,                // Self-reported, set once (0 = unset)
+    pub age_graded_total: u64,          // Sum of scores times the age multiplier at submission
+    pub region: [u8; 2],                // ISO 3166-1 alpha-2 country code ([0, 0] = unset)
+    pub region_joined: bool,            // Counted as a participant in region's RegionStats
+    pub gym: Pubkey,                    // Affiliated Gym (default = none)
+    pub recent_submissions: [RecentSubmission; RECENT_SUBMISSIONS_LEN], // Ring buffer for the dedupe window
+    pub recent_submission_head: u8,     // Next recent_submissions slot to overwrite
+    pub coached_submissions: u64,       // Submissions endorsed by a coach agent
+    pub highest_milestone: u64,         // Largest milestone already announced
+    pub strict_total: u64,              // Lifetime strict reps (undecayed)
+    pub kipping_total: u64,             // Lifetime kipping reps
+    pub weighted_total: u64,            // Lifetime weighted reps
+    pub best_strict: u64,               // Best single strict submission
+    pub best_kipping: u64,              // Best single kipping submission
+    pub best_weighted: u64,             // Best single weighted submission
+    pub best_weighted_single: WeightedSet, // Submission with the best load-adjusted score
+    pub load_adjusted_total: u64,       // Sum of load_adjusted_score over submissions
+    pub best_single_set: u16,           // Most reps in one set
+    pub best_session_volume: u64,       // Largest set-by-set session total
+    pub bump: u8,                       // PDA bump (0 until set by submit_score or migrate_user_score)
+    pub streak_rest_days: u8,           // Weekday bits off the streak schedule (0 = train daily)
+    pub verified_total: u64,            // Lifetime reps from attested submissions (undecayed)
+}
+
+impl UserScore {
+    // Stored inverted so accounts that predate schedules expect every day
+    pub fn streak_schedule(&self) -> u8 {
+        !self.streak_rest_days & ALL_TRAINING_DAYS
+    }
+
+    pub fn record_variant(&mut self, variant: PullupVariant, score: u64) -> Result<()> {
+        let (total, best) = match variant {
+            PullupVariant::Strict => (&mut self.strict_total, &mut self.best_strict),
+            PullupVariant::Kipping => (&mut self.kipping_total, &mut self.best_kipping),
+            PullupVariant::Weighted => (&mut self.weighted_total, &mut self.best_weighted),
+        };
+        *total = total.checked_add(score).ok_or(LeaderboardError::ArithmeticOverflow)?;
+        *best = (*best).max(score);
+        Ok(())
+    }
+
+    pub fn record_load(&mut self, reps: u64, added_weight_kg: u16) -> Result<()> {
+        let adjusted = load_adjusted_score(reps, added_weight_kg);
+        self.load_adjusted_total = self
+            .load_adjusted_total
+            .checked_add(adjusted)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        let best = self.best_weighted_single;
+        if adjusted > load_adjusted_score(best.reps, best.added_weight_kg) {
+            self.best_weighted_single = WeightedSet { reps, added_weight_kg };
+        }
+        Ok(())
+    }
+
+    pub fn to_data(&self, today: u64) -> UserScoreData {
+        UserScoreData {
+            user: self.user,
+            total_score: self.total_score,
+            best_single_score: self.best_single_score,
+            submission_count: self.submission_count,
+            last_submission_time: self.last_submission_time,
+            first_submission_time: self.first_submission_time,
+            best_daily_total: self.best_daily_total,
+            best_daily_date: self.best_daily_date,
+            bodyweight_kg: self.bodyweight_kg,
+            normalized_total: self.normalized_total,
+            seven_day_total: self.rolling_total(today),
+            best_this_period: self.best_this_period,
+            period_start: self.period_start,
+            verified_total: self.verified_total,
+        }
+    }
+
+    // Lazy activity decay: activity_score shrinks by `decay_bps` for every
+    // whole week since activity_updated_at, so no crank has to visit every
+    // account. The checkpoint only advances by whole weeks, so the partial
+    // week carries over and the result matches decaying once per week.
+    pub fn decay_activity(&mut self, decay_bps: u16, now: u64) {
+        let weeks = now.saturating_sub(self.activity_updated_at) / SECONDS_PER_WEEK;
+        if weeks == 0 {
+            return;
+        }
+        self.activity_score = apply_decay(
+            self.activity_score,
+            decay_bps,
+            SECONDS_PER_WEEK,
+            weeks * SECONDS_PER_WEEK,
+        );
+        self.activity_updated_at += weeks * SECONDS_PER_WEEK;
+    }
+
+    // Sum of the buckets that fall within the ROLLING_DAYS window ending today.
+    // Buckets from skipped days still hold an old day number and are ignored.
+    pub fn rolling_total(&self, today: u64) -> u64 {
+        self.daily_buckets
+            .iter()
+            .filter(|bucket| bucket.day <= today && today - bucket.day < ROLLING_DAYS as u64)
+            .map(|bucket| bucket.total)
+            .sum()
+    }
+
+    // Accept a client nonce once. Nonces must strictly increase; a retry of
+    // one of the last RECENT_NONCES_LEN accepted is reported as a duplicate so
+    // the client can tell it already landed, and anything else at or below
+    // last_nonce is stale
+    pub fn accept_nonce(&mut self, nonce: u64) -> Result<()> {
+        require!(!self.recent_nonces.contains(&nonce), LeaderboardError::DuplicateSubmission);
+        require!(nonce > self.last_nonce, LeaderboardError::StaleNonce);
+        let slot = self.recent_nonce_head as usize % RECENT_NONCES_LEN;
+        self.recent_nonces[slot] = nonce;
+        self.recent_nonce_head = ((slot + 1) % RECENT_NONCES_LEN) as u8;
+        self.last_nonce = nonce;
+        Ok(())
+    }
+
+    // Fold another wallet's history into this account for execute_merge.
+    // Lifetime counters add up with overflow checks, bests and streaks keep
+    // the better of the two, same-day buckets add up and otherwise the later
+    // day wins, and settings this account never set are inherited. Nonces and
+    // the dedupe ring stay with this wallet, which signs from here on.
+    // activity_score must already be decayed to the same time on both sides
+    pub fn absorb(&mut self, old: &UserScore) -> Result<()> {
+        if self.submission_count == 0 {
+            self.first_submission_time = old.first_submission_time;
+            self.activity_updated_at = old.activity_updated_at;
+            self.streak_rest_days = old.streak_rest_days;
+        } else if old.submission_count > 0 {
+            self.first_submission_time = self.first_submission_time.min(old.first_submission_time);
+            self.activity_updated_at = self.activity_updated_at.min(old.activity_updated_at);
+        }
+
+        self.total_score = self
+            .total_score
+            .checked_add(old.total_score)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.submission_count = self
+            .submission_count
+            .checked_add(old.submission_count)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.normalized_total = self
+            .normalized_total
+            .checked_add(old.normalized_total)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.age_graded_total = self
+            .age_graded_total
+            .checked_add(old.age_graded_total)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.activity_score = self
+            .activity_score
+            .checked_add(old.activity_score)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.referral_count = self
+            .referral_count
+            .checked_add(old.referral_count)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.proof_count = self
+            .proof_count
+            .checked_add(old.proof_count)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.coached_submissions = self
+            .coached_submissions
+            .checked_add(old.coached_submissions)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.entry_fee_paid = self
+            .entry_fee_paid
+            .checked_add(old.entry_fee_paid)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+
+        self.best_single_score = self.best_single_score.max(old.best_single_score);
+        self.current_streak = self.current_streak.max(old.current_streak);
+        self.longest_streak = self.longest_streak.max(old.longest_streak);
+        self.highest_milestone = self.highest_milestone.max(old.highest_milestone);
+        if old.last_submission_time > self.last_submission_time {
+            self.last_submission_time = old.last_submission_time;
+            if old.proof_count > 0 {
+                self.last_proof_hash = old.last_proof_hash;
+            }
+        }
+
+        if old.today_day == self.today_day {
+            self.today_total = self
+                .today_total
+                .checked_add(old.today_total)
+                .ok_or(LeaderboardError::ArithmeticOverflow)?;
+            self.submissions_today = self
+                .submissions_today
+                .checked_add(old.submissions_today)
+                .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        } else if old.today_day > self.today_day {
+            self.today_day = old.today_day;
+            self.today_total = old.today_total;
+            self.submissions_today = old.submissions_today;
+        }
+        for (bucket, old_bucket) in self.daily_buckets.iter_mut().zip(old.daily_buckets.iter()) {
+            if old_bucket.day == bucket.day {
+                bucket.total = bucket
+                    .total
+                    .checked_add(old_bucket.total)
+                    .ok_or(LeaderboardError::ArithmeticOverflow)?;
+            } else if old_bucket.day > bucket.day {
+                *bucket = *old_bucket;
+            }
+        }
+        // A day both wallets trained on may now beat either day on its own
+        if old.best_daily_total > self.best_daily_total {
+            self.best_daily_total = old.best_daily_total;
+            self.best_daily_date = old.best_daily_date;
+        }
+        if self.today_total > self.best_daily_total {
+            self.best_daily_total = self.today_total;
+            self.best_daily_date = self.today_day;
+        }
+
+        if old.period_start == self.period_start {
+            self.best_this_period = self.best_this_period.max(old.best_this_period);
+        } else if old.period_start > self.period_start {
+            self.period_start = old.period_start;
+            self.best_this_period = old.best_this_period;
+        }
+
+        self.strict_total = self
+            .strict_total
+            .checked_add(old.strict_total)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.kipping_total = self
+            .kipping_total
+            .checked_add(old.kipping_total)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.weighted_total = self
+            .weighted_total
+            .checked_add(old.weighted_total)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.load_adjusted_total = self
+            .load_adjusted_total
+            .checked_add(old.load_adjusted_total)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.verified_total = self
+            .verified_total
+            .checked_add(old.verified_total)
+            .ok_or(LeaderboardError::ArithmeticOverflow)?;
+        self.best_strict = self.best_strict.max(old.best_strict);
+        self.best_kipping = self.best_kipping.max(old.best_kipping);
+        self.best_weighted = self.best_weighted.max(old.best_weighted);
+        self.best_single_set = self.best_single_set.max(old.best_single_set);
+        self.best_session_volume = self.best_session_volume.max(old.best_session_volume);
+        let (best, old_best) = (self.best_weighted_single, old.best_weighted_single);
+        if load_adjusted_score(old_best.reps, old_best.added_weight_kg)
+            > load_adjusted_score(best.reps, best.added_weight_kg)
+        {
+            self.best_weighted_single = old_best;
+        }
+
+        self.registered |= old.registered;
+        self.is_private |= old.is_private;
+        if self.bodyweight_kg == 0 {
+            self.bodyweight_kg = old.bodyweight_kg;
+        }
+        if self.birth_year == 0 {
+            self.birth_year = old.birth_year;
+        }
+        if self.referrer == Pubkey::default() {
+            self.referrer = old.referrer;
+        }
+        if self.region == [0, 0] {
+            self.region = old.region;
+            self.region_joined = old.region_joined;
+        }
+        Ok(())
+    }
+}
+
+// ========================= CONTEXTS =========================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    // The official board is a single PDA clients can derive without a lookup;
+    // a second initialize fails because the account already exists
+    #[account(
+        init,
+        payer = owner,
+        space = LEADERBOARD_SPACE,
+        seeds = [b"leaderboard", b"pullups"],
+        bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury", leaderboard.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    // Only the program's upgrade authority may create the official board
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::SolanaPullupsLeaderboard>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(owner.key())
+            @ LeaderboardError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloneLeaderboard<'info> {
+    // Boards created before is_initialized existed are recognised by a set owner
+    #[account(
+        constraint = source.is_initialized || source.owner != Pubkey::default()
+            @ LeaderboardError::SourceNotInitialized
+    )]
+    pub source: Account<'info, Leaderboard>,
+    #[account(init, payer = owner, space = LEADERBOARD_SPACE)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Treasury::INIT_SPACE,
+        seeds = [b"treasury", leaderboard.key().as_ref()],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(score: u32, client_nonce: u64, referrer: Pubkey)]
+pub struct SubmitScore<'info> {
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = USER_SCORE_SPACE,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    // Only required when the leaderboard charges a submission fee
+    #[account(
+        mut,
+        seeds = [b"treasury", leaderboard.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Option<Account<'info, Treasury>>,
+    // Only required when a referrer is supplied. The bump is re-derived since
+    // a referrer's account may predate the stored bump
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), referrer.as_ref()],
+        bump
+    )]
+    pub referrer_score: Option<Account<'info, UserScore>>,
+    /// CHECK: Created here when a proof is supplied; address is verified in the handler
+    #[account(mut)]
+    pub proof_record: Option<UncheckedAccount<'info>>,
+    // Only required on a user's first submission
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ParticipantIndex::INIT_SPACE,
+        seeds = [
+            b"participant_index",
+            leaderboard.key().as_ref(),
+            (leaderboard.next_participant_slot() / PARTICIPANT_PAGE_SIZE as u64).to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub participant_index: Option<Account<'info, ParticipantIndex>>,
+    // Written when the leaderboard has an activity log
+    #[account(
+        mut,
+        seeds = [b"activity_log", leaderboard.key().as_ref()],
+        bump
+    )]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+    // Only required for submit_score_via_cpi
+    #[account(
+        seeds = [b"cpi_program", leaderboard.key().as_ref(), cpi_program.program_id.as_ref()],
+        bump = cpi_program.bump
+    )]
+    pub cpi_program: Option<Account<'info, CpiProgram>>,
+    /// CHECK: Instructions sysvar, only required for submit_score_via_cpi and submit_signed_score
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+    // Required once the leaderboard has a histogram
+    #[account(
+        mut,
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump = histogram.bump
+    )]
+    pub histogram: Option<Account<'info, ScoreHistogram>>,
+    // Required while the user is affiliated with a gym
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + GymStats::INIT_SPACE,
+        seeds = [b"gym_stats", user_score.gym.as_ref(), leaderboard.key().as_ref()],
+        bump
+    )]
+    pub gym_stats: Option<Account<'info, GymStats>>,
+    // Required when the user has set a region
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + RegionStats::INIT_SPACE,
+        seeds = [b"region", leaderboard.key().as_ref(), user_score.region.as_ref()],
+        bump
+    )]
+    pub region_stats: Option<Account<'info, RegionStats>>,
+    // Only required for submit_signed_score
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + NonceTracker::INIT_SPACE,
+        seeds = [b"nonce_tracker", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub nonce_tracker: Option<Account<'info, NonceTracker>>,
+    // Only required when a new wallet arrives at a full leaderboard
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Waitlist::INIT_SPACE,
+        seeds = [b"waitlist", leaderboard.key().as_ref(), leaderboard.waitlist_tail.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub waitlist: Option<Account<'info, Waitlist>>,
+    /// CHECK: Owner, discriminator and is_active are verified in verify_coach_agent
+    pub coach_agent: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey)]
+pub struct AllowCpiProgram<'info> {
+    #[account(has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CpiProgram::INIT_SPACE,
+        seeds = [b"cpi_program", leaderboard.key().as_ref(), program_id.as_ref()],
+        bump
+    )]
+    pub cpi_program: Account<'info, CpiProgram>,
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey)]
+pub struct RevokeCpiProgram<'info> {
+    #[account(has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"cpi_program", leaderboard.key().as_ref(), program_id.as_ref()],
+        bump = cpi_program.bump
+    )]
+    pub cpi_program: Account<'info, CpiProgram>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeActivityLog<'info> {
+    #[account(has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<ActivityLog>(),
+        seeds = [b"activity_log", leaderboard.key().as_ref()],
+        bump
+    )]
+    pub activity_log: AccountLoader<'info, ActivityLog>,
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeHistogram<'info> {
+    #[account(mut, has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ScoreHistogram::INIT_SPACE,
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump
+    )]
+    pub histogram: Account<'info, ScoreHistogram>,
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProofRecord<'info> {
+    #[account(has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = leaderboard,
+        seeds = [b"proof", leaderboard.key().as_ref(), proof_record.proof_hash.as_ref()],
+        bump = proof_record.bump
+    )]
+    pub proof_record: Account<'info, ProofRecord>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+// Shared context for owner-only settings that touch nothing but the leaderboard
+#[derive(Accounts)]
+pub struct AdminContext<'info> {
+    #[account(mut, has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwnership<'info> {
+    #[account(
+        mut,
+        constraint = leaderboard.pending_owner == new_owner.key() @ LeaderboardError::NotPendingOwner
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    pub new_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterParticipant<'info> {
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = USER_SCORE_SPACE,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    // Only required when the leaderboard charges an entry fee
+    #[account(
+        mut,
+        seeds = [b"treasury", leaderboard.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Option<Account<'info, Treasury>>,
+    // Only required when the leaderboard is full
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Waitlist::INIT_SPACE,
+        seeds = [b"waitlist", leaderboard.key().as_ref(), leaderboard.waitlist_tail.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub waitlist: Option<Account<'info, Waitlist>>,
+}
+
+#[derive(Accounts)]
+pub struct PromoteFromWaitlist<'info> {
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        close = user,
+        has_one = leaderboard,
+        has_one = user,
+        seeds = [b"waitlist", leaderboard.key().as_ref(), leaderboard.waitlist_head.to_le_bytes().as_ref()],
+        bump = waitlist.bump
+    )]
+    pub waitlist: Account<'info, Waitlist>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    /// CHECK: receives the waitlist rent; checked against waitlist.user
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundEntry<'info> {
+    #[account(has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"treasury", leaderboard.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(mut)]
+    pub user: SystemAccount<'info>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EnableCouncil<'info> {
+    #[account(mut, has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Council::INIT_SPACE,
+        seeds = [b"council", leaderboard.key().as_ref()],
+        bump
+    )]
+    pub council: Account<'info, Council>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAction<'info> {
+    #[account(mut)]
+    pub council: Account<'info, Council>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", council.key().as_ref(), &council.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAction<'info> {
+    pub council: Account<'info, Council>,
+    #[account(mut, has_one = council)]
+    pub proposal: Account<'info, Proposal>,
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAction<'info> {
+    #[account(
+        mut,
+        constraint = leaderboard.owner == council.key() @ LeaderboardError::Unauthorized
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(mut, has_one = leaderboard)]
+    pub council: Account<'info, Council>,
+    #[account(mut, has_one = council, has_one = proposer, close = proposer)]
+    pub proposal: Account<'info, Proposal>,
+    /// CHECK: receives the proposal rent; checked against proposal.proposer
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateExerciseName<'info> {
+    #[account(mut, has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(moderator: Pubkey)]
+pub struct AddModerator<'info> {
+    #[account(has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Moderator::INIT_SPACE,
+        seeds = [b"moderator", leaderboard.key().as_ref(), moderator.as_ref()],
+        bump
+    )]
+    pub moderator_marker: Account<'info, Moderator>,
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(moderator: Pubkey)]
+pub struct RemoveModerator<'info> {
+    #[account(has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"moderator", leaderboard.key().as_ref(), moderator.as_ref()],
+        bump = moderator_marker.bump
+    )]
+    pub moderator_marker: Account<'info, Moderator>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey, submission_index: u64)]
+pub struct FlagSubmission<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(
+        init,
+        payer = moderator,
+        space = 8 + Flag::INIT_SPACE,
+        seeds = [
+            b"flag",
+            leaderboard.key().as_ref(),
+            user.as_ref(),
+            submission_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub flag: Account<'info, Flag>,
+    #[account(
+        seeds = [b"moderator", leaderboard.key().as_ref(), moderator.key().as_ref()],
+        bump = moderator_marker.bump
+    )]
+    pub moderator_marker: Account<'info, Moderator>,
+    #[account(mut)]
+    pub moderator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveFlag<'info> {
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), flag.user.as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(mut, close = authority, has_one = leaderboard)]
+    pub flag: Account<'info, Flag>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    // Required unless the authority is the leaderboard owner
+    #[account(
+        seeds = [b"moderator", leaderboard.key().as_ref(), authority.key().as_ref()],
+        bump = moderator_marker.bump
+    )]
+    pub moderator_marker: Option<Account<'info, Moderator>>,
+    // Required once the board has a histogram
+    #[account(
+        mut,
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump = histogram.bump
+    )]
+    pub histogram: Option<Account<'info, ScoreHistogram>>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSubmission<'info> {
+    #[account(
+        mut,
+        has_one = oracle @ LeaderboardError::Unauthorized,
+        constraint = leaderboard.oracle != Pubkey::default() @ LeaderboardError::Unauthorized
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user_score.user.as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    pub oracle: Signer<'info>,
+    // Required once the board has a histogram
+    #[account(
+        mut,
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump = histogram.bump
+    )]
+    pub histogram: Option<Account<'info, ScoreHistogram>>,
+}
+
+#[derive(Accounts)]
+pub struct ArchiveUserScore<'info> {
+    #[account(mut, has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        close = treasury,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user_score.user.as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(
+        mut,
+        seeds = [b"treasury", leaderboard.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    pub owner: Signer<'info>,
+    // Required when the user is counted in the histogram
+    #[account(
+        mut,
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump = histogram.bump
+    )]
+    pub histogram: Option<Account<'info, ScoreHistogram>>,
+    // Head of the waitlist, promoted into the freed spot; required while
+    // anyone is waiting and the board has room
+    #[account(
+        mut,
+        seeds = [b"waitlist", leaderboard.key().as_ref(), leaderboard.waitlist_head.to_le_bytes().as_ref()],
+        bump = waitlist.bump
+    )]
+    pub waitlist: Option<Account<'info, Waitlist>>,
+    // The head's UserScore; its address is checked in promote_into_freed_spot
+    #[account(mut)]
+    pub waitlist_score: Option<Account<'info, UserScore>>,
+    /// CHECK: receives the waitlist rent; checked against waitlist.user
+    #[account(mut)]
+    pub waitlist_user: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUserScore<'info> {
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    // Required when the user is counted in the histogram
+    #[account(
+        mut,
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump = histogram.bump
+    )]
+    pub histogram: Option<Account<'info, ScoreHistogram>>,
+    // Head of the waitlist, promoted into the freed spot; required while
+    // anyone is waiting and the board has room
+    #[account(
+        mut,
+        seeds = [b"waitlist", leaderboard.key().as_ref(), leaderboard.waitlist_head.to_le_bytes().as_ref()],
+        bump = waitlist.bump
+    )]
+    pub waitlist: Option<Account<'info, Waitlist>>,
+    // The head's UserScore; its address is checked in promote_into_freed_spot
+    #[account(mut)]
+    pub waitlist_score: Option<Account<'info, UserScore>>,
+    /// CHECK: receives the waitlist rent; checked against waitlist.user
+    #[account(mut)]
+    pub waitlist_user: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(mut, has_one = owner @ LeaderboardError::Unauthorized)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"treasury", leaderboard.key().as_ref()],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    /// CHECK: Any account may receive withdrawn lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdminSetUserScore<'info> {
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user_score.user.as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    pub authority: Signer<'info>,
+    // Required unless the authority is the leaderboard owner
+    #[account(
+        seeds = [b"moderator", leaderboard.key().as_ref(), authority.key().as_ref()],
+        bump = moderator_marker.bump
+    )]
+    pub moderator_marker: Option<Account<'info, Moderator>>,
+    // Required once the board has a histogram
+    #[account(
+        mut,
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump = histogram.bump
+    )]
+    pub histogram: Option<Account<'info, ScoreHistogram>>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyActivityDecay<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user_score.user.as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMerge<'info> {
+    #[account(mut)]
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        close = new_wallet,
+        seeds = [b"user_score", leaderboard.key().as_ref(), old_score.user.as_ref()],
+        bump = old_score.bump,
+        constraint = old_score.merge_target == new_wallet.key() @ LeaderboardError::MergeNotAuthorized
+    )]
+    pub old_score: Account<'info, UserScore>,
+    #[account(
+        init_if_needed,
+        payer = new_wallet,
+        space = USER_SCORE_SPACE,
+        seeds = [b"user_score", leaderboard.key().as_ref(), new_wallet.key().as_ref()],
+        bump
+    )]
+    pub new_score: Account<'info, UserScore>,
+    #[account(mut)]
+    pub new_wallet: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    // Required when either wallet is counted in the histogram
+    #[account(
+        mut,
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump = histogram.bump
+    )]
+    pub histogram: Option<Account<'info, ScoreHistogram>>,
+    // Head of the waitlist, promoted when the merge frees a spot; required
+    // while anyone is waiting and the board has room
+    #[account(
+        mut,
+        seeds = [b"waitlist", leaderboard.key().as_ref(), leaderboard.waitlist_head.to_le_bytes().as_ref()],
+        bump = waitlist.bump
+    )]
+    pub waitlist: Option<Account<'info, Waitlist>>,
+    // The head's UserScore; its address is checked in promote_into_freed_spot
+    #[account(mut)]
+    pub waitlist_score: Option<Account<'info, UserScore>>,
+    /// CHECK: receives the waitlist rent; checked against waitlist.user
+    #[account(mut)]
+    pub waitlist_user: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOwnScore<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateGym<'info> {
+    #[account(init, payer = admin, space = 8 + Gym::INIT_SPACE)]
+    pub gym: Account<'info, Gym>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinGym<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(mut)]
+    pub gym: Account<'info, Gym>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveGym<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump = user_score.bump,
+        constraint = user_score.gym == gym.key() @ LeaderboardError::NotGymMember
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(mut)]
+    pub gym: Account<'info, Gym>,
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveGymMember<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        mut,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user_score.user.as_ref()],
+        bump = user_score.bump,
+        constraint = user_score.gym == gym.key() @ LeaderboardError::NotGymMember
+    )]
+    pub user_score: Account<'info, UserScore>,
+    #[account(mut, has_one = admin @ LeaderboardError::Unauthorized)]
+    pub gym: Account<'info, Gym>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateLeaderboard<'info> {
+    /// CHECK: Left raw so a legacy layout can be resized; discriminator checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub leaderboard: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserScore<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    /// CHECK: Left raw so a legacy layout can be resized before it deserializes
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_score: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmitMyHistory<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        seeds = [b"user_score", leaderboard.key().as_ref(), user.key().as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+    pub user: Signer<'info>,
+    // Adds the user's entries from the recent-activity log when present
+    #[account(
+        seeds = [b"activity_log", leaderboard.key().as_ref()],
+        bump
+    )]
+    pub activity_log: Option<AccountLoader<'info, ActivityLog>>,
+}
+
+#[derive(Accounts)]
+pub struct GetUserScore<'info> {
+    pub user_score: Account<'info, UserScore>,
+}
+
+#[derive(Accounts)]
+#[instruction(page_index: u64)]
+pub struct GetParticipantPage<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        seeds = [b"participant_index", leaderboard.key().as_ref(), page_index.to_le_bytes().as_ref()],
+        bump = participant_index.bump
+    )]
+    pub participant_index: Account<'info, ParticipantIndex>,
+}
+
+#[derive(Accounts)]
+pub struct CompareUsers<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        seeds = [b"user_score", leaderboard.key().as_ref(), user_score_a.user.as_ref()],
+        bump = user_score_a.bump
+    )]
+    pub user_score_a: Account<'info, UserScore>,
+    #[account(
+        seeds = [b"user_score", leaderboard.key().as_ref(), user_score_b.user.as_ref()],
+        bump = user_score_b.bump
+    )]
+    pub user_score_b: Account<'info, UserScore>,
+}
+
+#[derive(Accounts)]
+pub struct GetUserRankExact<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        seeds = [b"user_score", leaderboard.key().as_ref(), user_score.user.as_ref()],
+        bump = user_score.bump
+    )]
+    pub user_score: Account<'info, UserScore>,
+}
+
+#[derive(Accounts)]
+pub struct GetPercentile<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+    #[account(
+        seeds = [b"histogram", leaderboard.key().as_ref()],
+        bump = histogram.bump
+    )]
+    pub histogram: Account<'info, ScoreHistogram>,
+}
+
+#[derive(Accounts)]
+pub struct GetStats<'info> {
+    pub leaderboard: Account<'info, Leaderboard>,
+}
+
+#[derive(Accounts)]
+pub struct GetProgramInfo {}
+
+// ========================= EVENTS =========================
+
+#[event]
+pub struct LeaderboardInitialized {
+    pub leaderboard: Pubkey,
+    pub exercise_name: String,
+    pub owner: Pubkey,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct GymCreated {
+    pub gym: Pubkey,
+    pub admin: Pubkey,
+    pub name: String,
+}
+
+#[event]
+pub struct GymJoined {
+    pub gym: Pubkey,
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct GymLeft {
+    pub gym: Pubkey,
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct GymMemberRemoved {
+    pub gym: Pubkey,
+    pub user: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub leaderboard: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub total_withdrawn: u64,
+}
+
+#[event]
+pub struct LeaderboardCloned {
+    pub source: Pubkey,
+    pub clone: Pubkey,
+}
+
+#[event]
+pub struct ScoreSubmitted {
+    pub user: Pubkey,
+    pub score_added: u64,
+    pub new_total_score: u64,
+    pub new_best_score: u64,
+    pub timestamp: u64,
+    pub client_nonce: u64,
+    pub leaderboard: Pubkey,
+    pub exercise: String,
+    pub submission_index: u64,
+    pub proof_hash: [u8; 32],
+    pub calling_program: Pubkey,    // Default unless submitted via CPI
+    pub coach_agent: Pubkey,        // Endorsing AgentProfile, default if uncoached
+    pub variant: PullupVariant,
+    pub added_weight_kg: u16,       // 0 for bodyweight
+    pub sets: Vec<u16>,             // Reps per set, empty if not broken down
+    pub verified: bool,             // Counted under an oracle attestation
+}
+
+#[event]
+pub struct ModeratorAdded {
+    pub leaderboard: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[event]
+pub struct ModeratorRemoved {
+    pub leaderboard: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[event]
+pub struct SubmissionFlagged {
+    pub user: Pubkey,
+    pub submission_index: u64,
+    pub reason: u8,
+    pub moderator: Pubkey,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct FlagResolved {
+    pub user: Pubkey,
+    pub submission_index: u64,
+    pub valid: bool,
+    pub reversed_amount: u64,
+    pub resolver: Pubkey,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct SubmissionRevoked {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub reason: u8,
+    pub oracle: Pubkey,
+    pub new_total_score: u64,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct UserScoreArchived {
+    pub leaderboard: Pubkey,
+    pub data: UserScoreData,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct UserScoreClosed {
+    pub leaderboard: Pubkey,
+    pub data: UserScoreData,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct SubmissionWindowReset {
+    pub leaderboard: Pubkey,
+    pub all_time_submissions: u64,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct ExerciseRenamed {
+    pub old: String,
+    pub new: String,
+}
+
+#[event]
+pub struct AdminScoreAdjusted {
+    pub user: Pubkey,
+    pub admin: Pubkey,
+    pub old_total_score: u64,
+    pub new_total_score: u64,
+    pub old_best_single_score: u64,
+    pub new_best_single_score: u64,
+    pub old_submission_count: u64,
+    pub new_submission_count: u64,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct MergeAuthorized {
+    pub leaderboard: Pubkey,
+    pub old_wallet: Pubkey,
+    pub new_wallet: Pubkey,
+}
+
+#[event]
+pub struct AccountsMerged {
+    pub leaderboard: Pubkey,
+    pub old_wallet: Pubkey,
+    pub new_wallet: Pubkey,
+    pub new_total_score: u64,
+    pub new_submission_count: u64,
+}
+
+#[event]
+pub struct ScoreFrozen {
+    pub user: Pubkey,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct UnfreezeRequested {
+    pub user: Pubkey,
+    pub unlocks_at: u64,
+}
+
+#[event]
+pub struct ScoreUnfrozen {
+    pub user: Pubkey,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct ReferralCredited {
+    pub referrer: Pubkey,
+    pub referee: Pubkey,
+}
+
+#[event]
+pub struct MilestoneReached {
+    pub user: Pubkey,
+    pub milestone: u64,
+    pub total_score: u64,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct PersonalRecord {
+    pub user: Pubkey,
+    pub previous_best: u64,         // 0 on a user's first submission
+    pub new_best: u64,
+    pub improvement: u64,
+}
+
+#[event]
+pub struct DailyBestUpdated {
+    pub user: Pubkey,
+    pub best_daily_total: u64,
+    pub best_daily_date: u64,
+    pub timestamp: u64,
+    pub previous_best_daily_total: u64,
+}
+
+#[event]
+pub struct ParticipantRegistered {
+    pub leaderboard: Pubkey,
+    pub user: Pubkey,
+    pub entry_fee: u64,
+}
+
+#[event]
+pub struct WaitlistJoined {
+    pub leaderboard: Pubkey,
+    pub user: Pubkey,
+    pub position: u64,
+}
+
+#[event]
+pub struct PromotedFromWaitlist {
+    pub leaderboard: Pubkey,
+    pub user: Pubkey,
+    pub position: u64,
+}
+
+#[event]
+pub struct CompetitionCancelled {
+    pub leaderboard: Pubkey,
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct EntryRefunded {
+    pub leaderboard: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OwnershipTransferStarted {
+    pub leaderboard: Pubkey,
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,          // Default when an offer is cancelled
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub leaderboard: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct CouncilEnabled {
+    pub leaderboard: Pubkey,
+    pub council: Pubkey,
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct ActionProposed {
+    pub council: Pubkey,
+    pub proposal_index: u64,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct ActionApproved {
+    pub council: Pubkey,
+    pub proposal_index: u64,
+    pub approver: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ActionExecuted {
+    pub council: Pubkey,
+    pub proposal_index: u64,
+    pub executor: Pubkey,
+}
+
+#[event]
+pub struct UserDataExport {
+    pub leaderboard: Pubkey,
+    pub data: UserScoreData,
+    pub current_streak: u64,
+    pub longest_streak: u64,
+    pub daily_buckets: [DailyBucket; ROLLING_DAYS],
+    pub recent_sessions: Vec<SessionEntry>,     // Newest first
+    pub timestamp: u64,
+}
+
+#[event]
+pub struct VisibilityChanged {
+    pub user: Pubkey,
+    pub is_public: bool,
+}
+
+// ========================= DATA TYPES =========================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UserScoreData {
+    pub user: Pubkey,
+    pub total_score: u64,
+    pub best_single_score: u64,
+    pub submission_count: u64,
+    pub last_submission_time: u64,
+    pub first_submission_time: u64,
+    pub best_daily_total: u64,
+    pub best_daily_date: u64,
+    pub bodyweight_kg: u16,
+    pub normalized_total: u64,
+    pub seven_day_total: u64,
+    pub best_this_period: u64,          // Only current if period_start is the current period
+    pub period_start: u64,
+    pub verified_total: u64,            // Attested share of the user's reps
+}
+
+// The user's own ActivityLog entries, as carried by UserDataExport
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SessionEntry {
+    pub score: u64,
+    pub timestamp: u64,
+    pub submission_index: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct AgeGrade {
+    pub min_age: u8,                    // Bracket applies from this age
+    pub multiplier_bps: u16,            // 10_000 = 1.0; 0 marks an unused slot
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct DailyBucket {
+    pub day: u64,                       // UTC day number
+    pub total: u64,                     // Score submitted on that day
+}
+
+// Leading fields of the registry's AgentProfile, up to is_active
+#[derive(AnchorDeserialize)]
+pub struct AgentProfilePrefix {
+    pub authority: Pubkey,
+    pub name: String,
+    pub endpoint: String,
+    pub capabilities: Vec<String>,
+    pub base_fee: u64,
+    pub asset_mint: Pubkey,
+    pub reputation_score: u64,
+    pub total_jobs: u64,
+    pub registered_at: i64,
+    pub is_active: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct RecentSubmission {
+    pub score: u32,                     // Score as submitted
+    pub timestamp: u64,                 // When it was accepted
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Comparison {
+    pub leader: Pubkey,                 // User ahead after tie-breaks
+    pub total_score_delta: u64,         // Leader's ranking score minus the other's
+    pub tied: bool,                     // Every tie-break field is equal
+}
+
+// Operator-tunable settings passed to initialize
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct LeaderboardConfig {
+    pub submission_fee_lamports: u64,   // 0 disables the fee
+    pub decay_rate_bps: u16,            // 0 disables decay
+    pub decay_period: u64,              // Seconds per decay period
+    pub lock_name_after_first_submission: bool,
+    pub require_proof: bool,
+    pub max_flags: u8,                  // 0 = flags never block submissions
+    pub activity_decay_bps: u16,        // Weekly activity decay, 0 disables it
+    pub end_time: u64,                  // 0 = open-ended
+    pub enforce_unfreeze_delay: bool,
+    pub oracle: Pubkey,                 // Default disables oracle instructions
+    pub scoring_mode: ScoringMode,
+    pub archive_retention_seconds: u64, // 0 disables archiving, else >= 180 days
+    pub starts_at: u64,                 // 0 = open immediately
+    pub registration_opens_at: u64,     // 0 = registration open immediately
+    pub entry_fee_lamports: u64,        // 0 disables the entry fee
+    pub registered_only: bool,          // Require register_participant before submitting
+    pub max_participants: u32,          // 0 = no cap
+    pub best_period_seconds: u64,       // e.g. SECONDS_PER_DAY for a daily PR, 0 = weekly
+    pub max_submissions_per_day: u32,   // 0 = unlimited
+    pub strict_flags: u32,              // Extra requirement bits, ORed with the settings above
+    pub dedupe_window: u64,             // Seconds, 0 disables score/timestamp dedupe
+    pub decimals: u8,                   // Fixed-point scale of scores, at most 9
+    pub max_single_submission: u32,     // 0 = DEFAULT_MAX_SINGLE_SUBMISSION
+    pub mask_pubkeys: bool,             // Emit masked user ids instead of wallets
+    pub submission_cooldown: u64,       // Seconds, 0 = no cooldown
+}
+
+// Admin changes a council can vote on
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum AdminAction {
+    UpdateConfig(LeaderboardConfig),
+    SetSubmissionWindow { starts_at: u64, end_time: u64 },
+    SetCouncil {
+        #[max_len(MAX_COUNCIL_OWNERS)]
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    },
+    TransferOwnership(Pubkey),          // Hand the board back to a single key
+    Invoke {
+        #[max_len(MAX_PROPOSAL_ACCOUNTS)]
+        accounts: Vec<ProposalAccount>,
+        #[max_len(MAX_PROPOSAL_DATA_LEN)]
+        data: Vec<u8>,                  // Instruction data for this program
+    },
+}
+
+// One account of an AdminAction::Invoke; list the council as a signer where
+// the instruction expects the owner
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ProposalAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+// How users are ranked; every submission is still recorded in full
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, InitSpace)]
+pub enum ScoringMode {
+    #[default]
+    Cumulative,                         // Rank by total_score
+    Best,                               // Rank by best_single_score
+    LongestStreak,                      // Rank by longest_streak
+    AgeGraded,                          // Rank by age_graded_total
+    StrictOnly,                         // Rank by strict_total
+    LoadAdjusted,                       // Rank by load_adjusted_total
+    VerifiedOnly,                       // Rank by verified_total
+    Normalized,                         // Rank by normalized_total (bodyweight-adjusted)
+    Activity,                           // Rank by activity_score, decayed to now
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct WeightedSet {
+    pub reps: u64,                      // Reps in the submission
+    pub added_weight_kg: u16,           // Load on top of bodyweight
+}
+
+// How a pullups session was performed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PullupVariant {
+    #[default]
+    Strict,                             // Dead hang, no swing
+    Kipping,                            // Hip drive allowed
+    Weighted,                           // Strict with added load
+}
+
+impl ScoringMode {
+    pub fn ranking_score(&self, user_score: &UserScore) -> u64 {
+        match self {
+            ScoringMode::Cumulative => user_score.total_score,
+            ScoringMode::Best => user_score.best_single_score,
+            ScoringMode::LongestStreak => user_score.longest_streak,
+            ScoringMode::AgeGraded => user_score.age_graded_total,
+            ScoringMode::StrictOnly => user_score.strict_total,
+            ScoringMode::LoadAdjusted => user_score.load_adjusted_total,
+            ScoringMode::VerifiedOnly => user_score.verified_total,
+            ScoringMode::Normalized => user_score.normalized_total,
+            ScoringMode::Activity => user_score.activity_score,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProgramInfo {
+    pub version: u16,                   // PROGRAM_VERSION
+    pub features_bitmask: u64,          // FEATURE_* bits
+}
+
+impl ProgramInfo {
+    pub fn current() -> Self {
+        ProgramInfo {
+            version: PROGRAM_VERSION,
+            features_bitmask: FEATURES,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LeaderboardStats {
+    pub exercise_name: String,
+    pub total_participants: u64,
+    pub total_submissions: u64,         // Since the last reset_submission_window
+    pub all_time_submissions: u64,
+    pub total_score_all_users: u64,
+    pub record_holder: Pubkey,
+    pub record_score: u64,
+    pub decimals: u8,                   // Divide scores by 10^decimals to display them
+    pub cancelled: bool,                // Competition called off; submissions are closed
+    pub end_time: u64,                  // Submissions close after this time (0 = open-ended)
+}
+
+// ========================= ERRORS =========================
+
+#[error_code]
+pub enum LeaderboardError {
+    #[msg("This submission has already been recorded.")]
+    DuplicateSubmission,
+    #[msg("Client nonce must be greater than the last accepted nonce.")]
+    StaleNonce,
+    #[msg("You are not authorized to perform this action.")]
+    Unauthorized,
+    #[msg("The treasury account is required when a submission fee is set.")]
+    TreasuryRequired,
+    #[msg("The treasury does not hold enough lamports for this withdrawal.")]
+    InsufficientTreasuryBalance,
+    #[msg("Bodyweight is outside the accepted range.")]
+    InvalidBodyweight,
+    #[msg("Arithmetic overflow.")]
+    ArithmeticOverflow,
+    #[msg("A referrer can only be set on the first submission.")]
+    ReferralNotAllowed,
+    #[msg("You cannot refer yourself.")]
+    SelfReferral,
+    #[msg("The referrer's score account is required.")]
+    ReferrerAccountRequired,
+    #[msg("Decay rate cannot exceed 10000 basis points.")]
+    InvalidDecayConfig,
+    #[msg("Exercise name must be at most 32 bytes.")]
+    InvalidExerciseName,
+    #[msg("The exercise name is locked once submissions exist.")]
+    ExerciseNameLocked,
+    #[msg("This leaderboard requires a proof hash with every submission.")]
+    ProofRequired,
+    #[msg("The proof record account for this proof hash is required.")]
+    ProofRecordRequired,
+    #[msg("This proof hash has already been used on this leaderboard.")]
+    ProofAlreadyUsed,
+    #[msg("Proof records must be kept until the retention window has passed.")]
+    ProofRetentionActive,
+    #[msg("Participant count cannot go below zero.")]
+    ParticipantUnderflow,
+    #[msg("This account has unresolved flags and is under review.")]
+    UnderReview,
+    #[msg("The user has no submission with that index.")]
+    InvalidSubmissionIndex,
+    #[msg("The participant index page is required on a first submission.")]
+    ParticipantIndexRequired,
+    #[msg("This event has ended and no longer accepts submissions.")]
+    EventEnded,
+    #[msg("This program is not allowed to submit scores via CPI.")]
+    CpiCallerNotAllowed,
+    #[msg("This account is frozen by its owner.")]
+    AccountFrozen,
+    #[msg("This account is not frozen.")]
+    AccountNotFrozen,
+    #[msg("The unfreeze delay has not elapsed yet.")]
+    UnfreezeDelayActive,
+    #[msg("A wallet cannot merge into itself.")]
+    InvalidMergeTarget,
+    #[msg("The old wallet has not authorized a merge into this wallet.")]
+    MergeNotAuthorized,
+    #[msg("Archive retention must be 0 or at least 180 days.")]
+    RetentionTooShort,
+    #[msg("Archiving is disabled for this leaderboard.")]
+    ArchivingDisabled,
+    #[msg("This user submitted within the retention period.")]
+    UserStillActive,
+    #[msg("Submissions are not open yet.")]
+    SubmissionWindowClosed,
+    #[msg("The submission window can only change before the first submission.")]
+    SubmissionWindowLocked,
+    #[msg("The submission window must end after it starts.")]
+    InvalidSubmissionWindow,
+    #[msg("Council needs 1-10 distinct owners and a threshold no larger than the owner count.")]
+    InvalidCouncil,
+    #[msg("Signer is not a member of this council.")]
+    NotCouncilMember,
+    #[msg("This member has already approved the proposal.")]
+    AlreadyApproved,
+    #[msg("Not enough council approvals to execute.")]
+    ThresholdNotMet,
+    #[msg("Registration is not open.")]
+    RegistrationClosed,
+    #[msg("This wallet is already registered.")]
+    AlreadyRegistered,
+    #[msg("Only registered participants may submit to this leaderboard.")]
+    NotRegistered,
+    #[msg("This competition has been cancelled.")]
+    CompetitionCancelled,
+    #[msg("Only a competition with a future start time can be cancelled.")]
+    CompetitionStarted,
+    #[msg("Entry fees are only refunded after cancellation.")]
+    CompetitionNotCancelled,
+    #[msg("No entry fee to refund.")]
+    NothingToRefund,
+    #[msg("This leaderboard has already been initialized.")]
+    AlreadyInitialized,
+    #[msg("This wallet is already on the waitlist.")]
+    AlreadyWaitlisted,
+    #[msg("The leaderboard is full; pass the next waitlist account to queue.")]
+    WaitlistRequired,
+    #[msg("No free spot to promote into.")]
+    LeaderboardFull,
+    #[msg("Histogram bounds must start at 0 and strictly increase.")]
+    InvalidHistogramBounds,
+    #[msg("This leaderboard keeps a histogram; pass its account.")]
+    HistogramRequired,
+    #[msg("The scoring mode can only change before the first submission.")]
+    ScoringModeLocked,
+    #[msg("Daily submission limit reached; try again tomorrow.")]
+    DailyLimitReached,
+    #[msg("This strict flag is not supported yet.")]
+    UnsupportedStrictFlag,
+    #[msg("Missing or invalid oracle signature for this score.")]
+    InvalidOracleSignature,
+    #[msg("Birth year has already been set.")]
+    BirthYearLocked,
+    #[msg("Birth year is outside the accepted range.")]
+    InvalidBirthYear,
+    #[msg("Age grades must be ascending by min_age with unused slots last.")]
+    InvalidAgeGrades,
+    #[msg("This attestation nonce was already used or is older than the replay window.")]
+    NonceReplayed,
+    #[msg("Signed submissions need the user's nonce tracker account.")]
+    NonceTrackerRequired,
+    #[msg("Exercise name cannot be empty.")]
+    EmptyExerciseName,
+    #[msg("Region must be a two-letter uppercase country code.")]
+    InvalidRegion,
+    #[msg("This user has a region; pass its RegionStats account.")]
+    RegionStatsRequired,
+    #[msg("Gym name must be between 1 and 32 bytes.")]
+    InvalidGymName,
+    #[msg("Leave your current gym before joining another.")]
+    AlreadyInGym,
+    #[msg("This user is not a member of the gym.")]
+    NotGymMember,
+    #[msg("This user belongs to a gym; pass its GymStats account.")]
+    GymStatsRequired,
+    #[msg("Coach agent is not an AgentProfile from the agent registry.")]
+    InvalidCoachAgent,
+    #[msg("Coach agent is not active.")]
+    CoachAgentInactive,
+    #[msg("Score decimals must be at most 9.")]
+    InvalidDecimals,
+    #[msg("Score decimals cannot change once scores have been submitted.")]
+    DecimalsLocked,
+    #[msg("Score must be positive and within the leaderboard's single-submission cap.")]
+    ScoreOutOfRange,
+    #[msg("Milestones must be ascending with unused slots set to 0 at the end.")]
+    InvalidMilestones,
+    #[msg("Added weight must be at most 100 kg and only on weighted pullups.")]
+    InvalidAddedWeight,
+    #[msg("Sets must be 1-10 entries of 1-100 reps that add up to the score.")]
+    InvalidSets,
+    #[msg("Rank accounts must be this leaderboard's UserScore accounts.")]
+    InvalidRankAccount,
+    #[msg("Wait for the submission cooldown before submitting again.")]
+    SubmissionCooldown,
+    #[msg("A streak schedule needs at least one of the seven weekday bits.")]
+    InvalidStreakSchedule,
+    #[msg("Only the pending owner can accept this leaderboard.")]
+    NotPendingOwner,
+    #[msg("This participant index page is full.")]
+    ParticipantPageFull,
+    #[msg("The proposal's instruction is too large or its accounts don't match.")]
+    InvalidProposal,
+    #[msg("Only an initialized leaderboard can be cloned.")]
+    SourceNotInitialized,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nonce_tracker() -> NonceTracker {
+        NonceTracker {
+            leaderboard: Pubkey::default(),
+            user: Pubkey::default(),
+            window_start: 0,
+            bitmap: [0; 4],
+            bump: 0,
+        }
+    }
+
+    fn replayed() -> Result<()> {
+        Err(LeaderboardError::NonceReplayed.into())
+    }
+
+    #[test]
+    fn nonce_replay_within_window_is_rejected() {
+        let mut tracker = nonce_tracker();
+        assert!(tracker.mark(10).is_ok());
+        assert!(tracker.mark(3).is_ok());
+        assert_eq!(tracker.mark(3), replayed());
+        assert_eq!(tracker.mark(10), replayed());
+        assert!(tracker.mark(NONCE_WINDOW_BITS - 1).is_ok());
+        assert_eq!(tracker.window_start, 0);
+    }
+
+    #[test]
+    fn nonce_past_window_slides_it_and_keeps_recent_marks() {
+        let mut tracker = nonce_tracker();
+        assert!(tracker.mark(100).is_ok());
+        // Slides by exactly one word, then by a partial word
+        assert!(tracker.mark(NONCE_WINDOW_BITS + 63).is_ok());
+        assert_eq!(tracker.window_start, 64);
+        assert!(tracker.mark(NONCE_WINDOW_BITS + 100).is_ok());
+        assert_eq!(tracker.window_start, 101);
+        assert_eq!(tracker.mark(NONCE_WINDOW_BITS + 63), replayed());
+        assert_eq!(tracker.mark(NONCE_WINDOW_BITS + 100), replayed());
+        assert!(tracker.mark(101).is_ok());
+    }
+
+    #[test]
+    fn nonce_shift_larger_than_window_clears_bitmap() {
+        let mut tracker = nonce_tracker();
+        assert!(tracker.mark(5).is_ok());
+        assert!(tracker.mark(10_000).is_ok());
+        assert_eq!(tracker.window_start, 10_000 - (NONCE_WINDOW_BITS - 1));
+        assert_eq!(tracker.bitmap, [0, 0, 0, 1 << 63]);
+        assert!(tracker.mark(tracker.window_start).is_ok());
+    }
+
+    #[test]
+    fn nonce_below_window_start_is_rejected() {
+        let mut tracker = nonce_tracker();
+        assert!(tracker.mark(1_000).is_ok());
+        let window_start = tracker.window_start;
+        assert_eq!(tracker.mark(window_start - 1), replayed());
+        assert_eq!(tracker.mark(0), replayed());
+        assert_eq!(tracker.window_start, window_start);
+    }
+
+    #[test]
+    fn decay_disabled_or_within_first_period_is_unchanged() {
+        assert_eq!(apply_decay(1_000, 0, 10, 1_000), 1_000);
+        assert_eq!(apply_decay(1_000, 1_000, 0, 1_000), 1_000);
+        assert_eq!(apply_decay(1_000, 1_000, 10, 9), 1_000);
+        assert_eq!(apply_decay(0, 1_000, 10, 1_000), 0);
+    }
+
+    #[test]
+    fn decay_compounds_per_whole_period() {
+        assert_eq!(apply_decay(1_000, 1_000, 10, 10), 900);
+        assert_eq!(apply_decay(1_000, 1_000, 10, 25), 810);
+        assert_eq!(apply_decay(1_000, 1_000, 10, 30), 729);
+    }
+
+    #[test]
+    fn decay_extremes() {
+        assert_eq!(apply_decay(1_000, BPS_DENOMINATOR as u16, 10, 10), 0);
+        assert_eq!(apply_decay(1_000, 5_000, 1, u64::MAX), 0);
+        assert_eq!(apply_decay(u64::MAX, 5_000, 10, 10), u64::MAX / 2);
+    }
+
+    // Day 4 of the epoch was a Monday
+    const MONDAY: u64 = 4;
+
+    #[test]
+    fn weekday_of_counts_from_monday() {
+        assert_eq!(weekday_of(0), 3);
+        assert_eq!(weekday_of(MONDAY), 0);
+        assert_eq!(weekday_of(MONDAY + 6), 6);
+        assert_eq!(weekday_of(MONDAY + 7), 0);
+    }
+
+    #[test]
+    fn daily_schedule_misses_any_skipped_day() {
+        assert!(!missed_training_day(ALL_TRAINING_DAYS, MONDAY, MONDAY));
+        assert!(!missed_training_day(ALL_TRAINING_DAYS, MONDAY, MONDAY + 1));
+        assert!(missed_training_day(ALL_TRAINING_DAYS, MONDAY, MONDAY + 2));
+    }
+
+    #[test]
+    fn rest_days_do_not_break_the_streak() {
+        let mon_wed_fri = 0b001_0101;
+        // Monday to Wednesday skips only Tuesday
+        assert!(!missed_training_day(mon_wed_fri, MONDAY, MONDAY + 2));
+        // Friday to Monday skips the weekend
+        assert!(!missed_training_day(mon_wed_fri, MONDAY + 4, MONDAY + 7));
+        // Monday to Friday skips Wednesday
+        assert!(missed_training_day(mon_wed_fri, MONDAY, MONDAY + 4));
+    }
+
+    #[test]
+    fn a_week_long_gap_always_misses() {
+        let mondays = 0b000_0001;
+        assert!(!missed_training_day(mondays, MONDAY, MONDAY + 7));
+        assert!(missed_training_day(mondays, MONDAY, MONDAY + 8));
+        assert!(missed_training_day(mondays, MONDAY, u64::MAX));
+    }
+
+    fn user_score() -> UserScore {
+        UserScore::try_deserialize_unchecked(&mut &[0u8; USER_SCORE_SPACE][..]).unwrap()
+    }
+
+    fn record_day(user_score: &mut UserScore, day: u64, total: u64) {
+        user_score.daily_buckets[(day % ROLLING_DAYS as u64) as usize] = DailyBucket { day, total };
+    }
+
+    #[test]
+    fn rolling_total_sums_the_last_seven_days() {
+        let mut user_score = user_score();
+        for day in 94..=100 {
+            record_day(&mut user_score, day, day);
+        }
+        assert_eq!(user_score.rolling_total(100), (94..=100).sum::<u64>());
+        assert_eq!(user_score.rolling_total(101), (95..=100).sum::<u64>());
+        assert_eq!(user_score.rolling_total(107), 0);
+    }
+
+    #[test]
+    fn rolling_total_ignores_stale_and_future_buckets() {
+        let mut user_score = user_score();
+        record_day(&mut user_score, 90, 5);
+        record_day(&mut user_score, 99, 7);
+        record_day(&mut user_score, 102, 11);
+        assert_eq!(user_score.rolling_total(100), 7);
+        assert_eq!(user_score.rolling_total(0), 0);
+    }
+
+    #[test]
+    fn activity_decay_spans_multi_week_gaps_and_carries_partial_weeks() {
+        let mut user_score = user_score();
+        user_score.activity_score = 1_000;
+        user_score.decay_activity(1_000, SECONDS_PER_WEEK - 1);
+        assert_eq!(user_score.activity_score, 1_000);
+
+        user_score.decay_activity(1_000, 2 * SECONDS_PER_WEEK + 3 * SECONDS_PER_DAY);
+        assert_eq!(user_score.activity_score, 810);
+        assert_eq!(user_score.activity_updated_at, 2 * SECONDS_PER_WEEK);
+
+        // The carried-over partial week counts once it completes
+        user_score.decay_activity(1_000, 3 * SECONDS_PER_WEEK);
+        assert_eq!(user_score.activity_score, 729);
+        assert_eq!(user_score.activity_updated_at, 3 * SECONDS_PER_WEEK);
+    }
+
+    #[test]
+    fn client_nonce_repeat_is_a_duplicate() {
+        let mut user_score = user_score();
+        assert!(user_score.accept_nonce(7).is_ok());
+        assert_eq!(user_score.accept_nonce(7), Err(LeaderboardError::DuplicateSubmission.into()));
+    }
+
+    #[test]
+    fn out_of_order_client_nonce_is_stale() {
+        let mut user_score = user_score();
+        assert!(user_score.accept_nonce(10).is_ok());
+        assert_eq!(user_score.accept_nonce(9), Err(LeaderboardError::StaleNonce.into()));
+        assert!(user_score.accept_nonce(12).is_ok());
+        assert_eq!(user_score.accept_nonce(11), Err(LeaderboardError::StaleNonce.into()));
+        assert_eq!(user_score.last_nonce, 12);
+    }
+
+    #[test]
+    fn only_the_last_few_nonces_are_reported_as_duplicates() {
+        let mut user_score = user_score();
+        for nonce in 1..=RECENT_NONCES_LEN as u64 + 1 {
+            assert!(user_score.accept_nonce(nonce).is_ok());
+        }
+        // Nonce 1 was overwritten by the ring, so its retry is merely stale
+        assert_eq!(user_score.accept_nonce(1), Err(LeaderboardError::StaleNonce.into()));
+        assert_eq!(user_score.accept_nonce(2), Err(LeaderboardError::DuplicateSubmission.into()));
+        assert_eq!(user_score.recent_nonce_head, 1);
+    }
+
+    fn leaderboard() -> Leaderboard {
+        Leaderboard::try_deserialize_unchecked(&mut &[0u8; LEADERBOARD_SPACE][..]).unwrap()
+    }
+
+    fn config() -> LeaderboardConfig {
+        LeaderboardConfig::deserialize(&mut &[0u8; 256][..]).unwrap()
+    }
+
+    #[test]
+    fn window_reset_keeps_the_all_time_count() {
+        let mut leaderboard = leaderboard();
+        leaderboard.total_submissions = 5;
+        leaderboard.reset_window();
+        leaderboard.total_submissions = 2;
+        leaderboard.reset_window();
+        assert_eq!(leaderboard.total_submissions, 0);
+        assert_eq!(leaderboard.all_time_submissions(), 7);
+    }
+
+    #[test]
+    fn reversing_a_pre_reset_submission_comes_out_of_the_archive() {
+        let mut leaderboard = leaderboard();
+        leaderboard.total_submissions = 2;
+        leaderboard.total_score_all_users = 30;
+        leaderboard.reset_window();
+        let mut user_score = user_score();
+        user_score.total_score = 10;
+        user_score.best_single_score = 10;
+        user_score.submission_count = 1;
+
+        assert_eq!(reverse_submission(&mut leaderboard, &mut user_score, 4), Ok(4));
+        assert_eq!(leaderboard.total_submissions, 0);
+        assert_eq!(leaderboard.all_time_submissions(), 1);
+        assert_eq!(leaderboard.total_score_all_users, 26);
+        assert_eq!(user_score.best_single_score, 6);
+    }
+
+    #[test]
+    fn removed_submissions_empty_the_window_before_the_archive() {
+        let mut leaderboard = leaderboard();
+        leaderboard.archived_submissions = 3;
+        leaderboard.total_submissions = 1;
+        leaderboard.remove_submissions(2);
+        assert_eq!((leaderboard.total_submissions, leaderboard.archived_submissions), (0, 2));
+        leaderboard.remove_submissions(5);
+        assert_eq!(leaderboard.all_time_submissions(), 0);
+    }
+
+    #[test]
+    fn scoring_mode_stays_locked_after_a_window_reset() {
+        let mut leaderboard = leaderboard();
+        leaderboard.total_submissions = 3;
+        leaderboard.reset_window();
+        let mut config = config();
+        config.scoring_mode = ScoringMode::Best;
+        let locked = Err(LeaderboardError::ScoringModeLocked.into());
+        assert_eq!(leaderboard.apply_config(&config), locked);
+        config.scoring_mode = ScoringMode::Cumulative;
+        assert!(leaderboard.apply_config(&config).is_ok());
+    }
+
+    #[test]
+    fn exercise_names_are_trimmed_and_lowercased() {
+        assert_eq!(normalize_exercise_name("  Pullups "), Ok("pullups".to_string()));
+        let longest = "a".repeat(MAX_EXERCISE_NAME_LEN);
+        assert_eq!(normalize_exercise_name(&longest), Ok(longest.clone()));
+    }
+
+    #[test]
+    fn blank_or_overlong_exercise_names_are_rejected() {
+        assert_eq!(normalize_exercise_name("   "), Err(LeaderboardError::EmptyExerciseName.into()));
+        assert_eq!(
+            normalize_exercise_name(&"a".repeat(MAX_EXERCISE_NAME_LEN + 1)),
+            Err(LeaderboardError::InvalidExerciseName.into())
+        );
+    }
+
+    fn council(owners: &[Pubkey], threshold: u8) -> Council {
+        Council {
+            leaderboard: Pubkey::new_unique(),
+            owners: owners.to_vec(),
+            threshold,
+            proposal_count: 0,
+            bump: 0,
+        }
+    }
+
+    fn proposal(approvals: &[Pubkey]) -> Proposal {
+        Proposal {
+            council: Pubkey::new_unique(),
+            index: 0,
+            proposer: approvals[0],
+            action: AdminAction::TransferOwnership(Pubkey::new_unique()),
+            approvals: approvals.to_vec(),
+            bump: 0,
+        }
+    }
+
+    fn invalid_council(owners: &[Pubkey], threshold: u8) -> bool {
+        Council::validate_members(owners, threshold) == Err(LeaderboardError::InvalidCouncil.into())
+    }
+
+    #[test]
+    fn council_threshold_can_equal_but_not_exceed_the_owner_count() {
+        let owners = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        assert!(Council::validate_members(&owners, 3).is_ok());
+        assert!(Council::validate_members(&owners, 1).is_ok());
+        assert!(invalid_council(&owners, 4));
+        assert!(invalid_council(&owners, 0));
+        assert!(invalid_council(&[], 0));
+    }
+
+    #[test]
+    fn council_rejects_duplicate_and_excess_owners() {
+        let owner = Pubkey::new_unique();
+        assert!(invalid_council(&[owner, Pubkey::new_unique(), owner], 2));
+        let too_many: Vec<_> = (0..=MAX_COUNCIL_OWNERS).map(|_| Pubkey::new_unique()).collect();
+        assert!(invalid_council(&too_many, 1));
+    }
+
+    #[test]
+    fn proposals_reach_the_threshold_only_with_enough_member_approvals() {
+        let owners = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let council = council(&owners, 2);
+        assert_eq!(council.approval_count(&proposal(&owners[..1])), 1);
+        assert_eq!(council.approval_count(&proposal(&owners[..2])), 2);
+        // A stranger's approval never counts toward the threshold
+        assert_eq!(council.approval_count(&proposal(&[owners[0], Pubkey::new_unique()])), 1);
+    }
+
+    #[test]
+    fn approvals_from_removed_owners_lapse() {
+        let owners = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let proposal = proposal(&owners[..2]);
+        let council = council(&owners[1..], 2);
+        assert_eq!(council.approval_count(&proposal), 1);
+    }
+
+    fn ranked(total_score: u64, best_single_score: u64, first_submission_time: u64) -> UserScore {
+        let mut user_score = user_score();
+        user_score.user = Pubkey::new_unique();
+        user_score.total_score = total_score;
+        user_score.best_single_score = best_single_score;
+        user_score.first_submission_time = first_submission_time;
+        user_score
+    }
+
+    // (leader, total_score_delta, tied)
+    fn compare(a: &UserScore, b: &UserScore, mode: ScoringMode) -> (Pubkey, u64, bool) {
+        let result = compare_user_scores(a, b, mode);
+        (result.leader, result.total_score_delta, result.tied)
+    }
+
+    #[test]
+    fn comparison_leader_is_ahead_by_the_ranking_score_delta() {
+        let (a, b) = (ranked(50, 5, 0), ranked(70, 5, 0));
+        assert_eq!(compare(&a, &b, ScoringMode::Cumulative), (b.user, 20, false));
+    }
+
+    #[test]
+    fn cumulative_ties_break_on_best_single_score() {
+        let (a, b) = (ranked(50, 10, 0), ranked(50, 12, 0));
+        assert_eq!(compare(&a, &b, ScoringMode::Cumulative), (b.user, 0, false));
+    }
+
+    #[test]
+    fn other_modes_break_ties_on_total_score() {
+        let (a, b) = (ranked(60, 20, 0), ranked(40, 20, 0));
+        assert_eq!(compare(&a, &b, ScoringMode::Best), (a.user, 0, false));
+    }
+
+    #[test]
+    fn earlier_first_submission_wins_remaining_ties() {
+        let (late, early) = (ranked(50, 10, 200), ranked(50, 10, 100));
+        assert_eq!(compare(&late, &early, ScoringMode::Cumulative), (early.user, 0, false));
+        let (a, b) = (ranked(50, 10, 100), ranked(50, 10, 100));
+        assert_eq!(compare(&a, &b, ScoringMode::Cumulative), (a.user, 0, true));
+    }
+
+    fn histogram() -> ScoreHistogram {
+        ScoreHistogram {
+            leaderboard: Pubkey::default(),
+            lower_bounds: std::array::from_fn(|i| i as u64 * 10),
+            counts: [0; HISTOGRAM_BUCKETS],
+            total: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn bucket_floors_are_inclusive() {
+        let histogram = histogram();
+        assert_eq!(histogram.bucket_for(0), 0);
+        assert_eq!(histogram.bucket_for(9), 0);
+        assert_eq!(histogram.bucket_for(10), 1);
+        assert_eq!(histogram.bucket_for(19), 1);
+        let last = HISTOGRAM_BUCKETS - 1;
+        assert_eq!(histogram.bucket_for(last as u64 * 10), last);
+        assert_eq!(histogram.bucket_for(u64::MAX), last);
+    }
+
+    #[test]
+    fn percentile_counts_users_in_lower_buckets() {
+        let mut histogram = histogram();
+        assert_eq!(histogram.percentile_bps(50), 0);
+        for best in [5, 15, 17, 25] {
+            histogram.add_user(best).unwrap();
+        }
+        assert_eq!(histogram.percentile_bps(0), 0);
+        // 15 and 17 share a bucket, so neither is below the other
+        assert_eq!(histogram.percentile_bps(15), 2_500);
+        assert_eq!(histogram.percentile_bps(19), 2_500);
+        assert_eq!(histogram.percentile_bps(25), 7_500);
+        assert_eq!(histogram.percentile_bps(u64::MAX), 10_000);
+    }
+
+    #[test]
+    fn moving_a_user_shifts_the_percentile() {
+        let mut histogram = histogram();
+        histogram.add_user(5).unwrap();
+        histogram.add_user(25).unwrap();
+        histogram.move_user(5, 35).unwrap();
+        assert_eq!(histogram.total, 2);
+        assert_eq!(histogram.percentile_bps(30), 5_000);
+        histogram.remove_user(35);
+        assert_eq!(histogram.counts.iter().sum::<u64>(), 1);
+        assert_eq!(histogram.percentile_bps(30), 10_000);
+    }
+
+    // 2024-01-01T00:00:00Z
+    const NEW_YEAR_2024: u64 = 1_704_067_200;
+
+    #[test]
+    fn year_of_rolls_over_at_utc_midnight() {
+        assert_eq!(year_of(0), 1970);
+        assert_eq!(year_of(NEW_YEAR_2024 - 1), 2023);
+        assert_eq!(year_of(NEW_YEAR_2024), 2024);
+        // 2024 is a leap year, so its last second is 366 days on
+        let new_year_2025 = NEW_YEAR_2024 + 366 * SECONDS_PER_DAY;
+        assert_eq!(year_of(new_year_2025 - 1), 2024);
+        assert_eq!(year_of(new_year_2025), 2025);
+    }
+
+    fn age_graded_leaderboard() -> Leaderboard {
+        let mut leaderboard = leaderboard();
+        leaderboard.age_grades[0] = AgeGrade { min_age: 35, multiplier_bps: 10_500 };
+        leaderboard.age_grades[1] = AgeGrade { min_age: 50, multiplier_bps: 11_500 };
+        leaderboard
+    }
+
+    #[test]
+    fn age_brackets_start_at_their_min_age() {
+        let leaderboard = age_graded_leaderboard();
+        assert_eq!(leaderboard.age_multiplier_bps(1990, NEW_YEAR_2024), 10_000);
+        assert_eq!(leaderboard.age_multiplier_bps(1989, NEW_YEAR_2024), 10_500);
+        assert_eq!(leaderboard.age_multiplier_bps(1975, NEW_YEAR_2024), 10_500);
+        assert_eq!(leaderboard.age_multiplier_bps(1974, NEW_YEAR_2024), 11_500);
+    }
+
+    #[test]
+    fn age_bracket_changes_with_the_calendar_year() {
+        let leaderboard = age_graded_leaderboard();
+        assert_eq!(leaderboard.age_multiplier_bps(1989, NEW_YEAR_2024 - 1), 10_000);
+        assert_eq!(leaderboard.age_multiplier_bps(1989, NEW_YEAR_2024), 10_500);
+    }
+
+    #[test]
+    fn missing_or_future_birth_year_competes_at_par() {
+        let leaderboard = age_graded_leaderboard();
+        assert_eq!(leaderboard.age_multiplier_bps(0, NEW_YEAR_2024), 10_000);
+        assert_eq!(leaderboard.age_multiplier_bps(2030, NEW_YEAR_2024), 10_000);
+    }
+
+    #[test]
+    fn unweighted_reps_score_as_is() {
+        assert_eq!(load_adjusted_score(12, 0), 12);
+        assert_eq!(load_adjusted_score(0, MAX_ADDED_WEIGHT_KG), 0);
+    }
+
+    #[test]
+    fn added_weight_scales_reps_and_rounds_down() {
+        // Each kg adds LOAD_PERCENT_PER_KG percent
+        assert_eq!(load_adjusted_score(10, 10), 12);
+        assert_eq!(load_adjusted_score(3, 1), 3);
+        assert_eq!(load_adjusted_score(50, 1), 51);
+        assert_eq!(load_adjusted_score(10, MAX_ADDED_WEIGHT_KG), 30);
+    }
+
+    fn invalid_sets(sets: &[u16], score: u32) -> bool {
+        validate_sets(sets, score) == Err(LeaderboardError::InvalidSets.into())
+    }
+
+    #[test]
+    fn sets_summing_to_the_score_are_accepted() {
+        assert!(validate_sets(&[10, 5], 15).is_ok());
+        assert!(validate_sets(&[MAX_REPS_PER_SET], MAX_REPS_PER_SET as u32).is_ok());
+        assert!(validate_sets(&[1; MAX_SETS], MAX_SETS as u32).is_ok());
+    }
+
+    #[test]
+    fn malformed_set_breakdowns_are_rejected() {
+        assert!(invalid_sets(&[], 0));
+        assert!(invalid_sets(&[10, 5], 14));
+        assert!(invalid_sets(&[10, 0, 5], 15));
+        assert!(invalid_sets(&[MAX_REPS_PER_SET + 1], MAX_REPS_PER_SET as u32 + 1));
+        assert!(invalid_sets(&[1; MAX_SETS + 1], MAX_SETS as u32 + 1));
+    }
+
+    #[test]
+    fn strict_flags_map_to_requirements() {
+        let mut leaderboard = leaderboard();
+        leaderboard.set_strict_flags(REQUIRE_ATTESTATION | ENFORCE_COOLDOWN).unwrap();
+        assert!(leaderboard.requires(REQUIRE_ATTESTATION));
+        assert!(leaderboard.requires(ENFORCE_COOLDOWN));
+        assert!(!leaderboard.requires(REQUIRE_PROOF_HASH));
+        assert!(!leaderboard.requires(ENFORCE_RATE_LIMITS));
+    }
+
+    #[test]
+    fn strict_flags_keep_the_legacy_booleans_in_step() {
+        let mut leaderboard = leaderboard();
+        leaderboard.set_strict_flags(REQUIRE_PROOF_HASH | REQUIRE_REGISTRATION).unwrap();
+        assert!(leaderboard.require_proof && leaderboard.registered_only);
+        leaderboard.set_strict_flags(0).unwrap();
+        assert!(!leaderboard.require_proof && !leaderboard.registered_only);
+    }
+
+    #[test]
+    fn boards_predating_strict_flags_still_require_their_booleans() {
+        let mut leaderboard = leaderboard();
+        leaderboard.require_proof = true;
+        leaderboard.registered_only = true;
+        assert!(leaderboard.requires(REQUIRE_PROOF_HASH));
+        assert!(leaderboard.requires(REQUIRE_REGISTRATION));
+        assert!(!leaderboard.requires(REQUIRE_ATTESTATION));
+    }
+
+    #[test]
+    fn unsupported_strict_flags_are_rejected() {
+        let mut leaderboard = leaderboard();
+        for flags in [REQUIRE_DURATION, REQUIRE_PROOF_HASH | 1 << 31] {
+            let rejection = Err(LeaderboardError::UnsupportedStrictFlag.into());
+            assert_eq!(leaderboard.set_strict_flags(flags), rejection);
+        }
+        assert_eq!(leaderboard.strict_flags, 0);
+    }
+
+    fn activity_entry(score: u64) -> ActivityEntry {
+        ActivityEntry { user: Pubkey::default(), score, timestamp: 0, submission_index: score }
+    }
+
+    #[test]
+    fn activity_log_wraps_over_its_oldest_entries() {
+        let mut log = ActivityLog {
+            leaderboard: Pubkey::default(),
+            head: 0,
+            entries: [activity_entry(0); ACTIVITY_LOG_LEN],
+        };
+        let written = ACTIVITY_LOG_LEN as u64 + 10;
+        for score in 0..written {
+            log.push(activity_entry(score));
+        }
+        assert_eq!(log.head, written);
+        let len = ACTIVITY_LOG_LEN as u64;
+        // Newest at (head - 1) % len, oldest surviving at head % len
+        assert_eq!(log.entries[((log.head - 1) % len) as usize].score, written - 1);
+        assert_eq!(log.entries[(log.head % len) as usize].score, 10);
+        assert_eq!(log.entries[0].score, len);
+        assert!(log.entries.iter().all(|entry| entry.score >= 10));
+    }
+
+    #[test]
+    fn participant_count_cannot_go_below_zero() {
+        let mut leaderboard = leaderboard();
+        leaderboard.increment_participants().unwrap();
+        leaderboard.decrement_participants().unwrap();
+        let underflow = Err(LeaderboardError::ParticipantUnderflow.into());
+        assert_eq!(leaderboard.decrement_participants(), underflow);
+        assert_eq!(leaderboard.total_participants, 0);
+    }
+
+    #[test]
+    fn participant_count_cannot_wrap_at_the_top() {
+        let mut leaderboard = leaderboard();
+        leaderboard.total_participants = u64::MAX;
+        let overflow = Err(LeaderboardError::ArithmeticOverflow.into());
+        assert_eq!(leaderboard.increment_participants(), overflow);
+        assert_eq!(leaderboard.total_participants, u64::MAX);
+    }
+
+    #[test]
+    fn program_info_reports_the_compiled_version_and_features() {
+        let info = ProgramInfo::current();
+        assert_eq!(info.version, PROGRAM_VERSION);
+        assert_eq!(info.features_bitmask, FEATURES);
+        assert_ne!(info.features_bitmask & FEATURE_ADMIN_COUNCIL, 0);
+    }
+
+    #[test]
+    fn decimals_up_to_nine_are_stored_and_reported() {
+        let mut leaderboard = leaderboard();
+        let mut config = config();
+        config.decimals = MAX_SCORE_DECIMALS;
+        assert!(leaderboard.apply_config(&config).is_ok());
+        assert_eq!(leaderboard.stats().decimals, MAX_SCORE_DECIMALS);
+
+        config.decimals = MAX_SCORE_DECIMALS + 1;
+        let invalid = Err(LeaderboardError::InvalidDecimals.into());
+        assert_eq!(leaderboard.apply_config(&config), invalid);
+    }
+
+    #[test]
+    fn decimals_lock_once_scores_exist() {
+        let mut leaderboard = leaderboard();
+        let mut config = config();
+        config.decimals = 2;
+        leaderboard.apply_config(&config).unwrap();
+        leaderboard.total_submissions = 1;
+        leaderboard.reset_window();
+
+        config.decimals = 3;
+        let locked = Err(LeaderboardError::DecimalsLocked.into());
+        assert_eq!(leaderboard.apply_config(&config), locked);
+        assert_eq!(leaderboard.stats().decimals, 2);
+    }
+
+    fn zeroed<T: AccountDeserialize>(space: usize) -> T {
+        T::try_deserialize_unchecked(&mut &vec![0u8; space][..]).unwrap()
+    }
+
+    // Serialize into an account of `space` bytes and read it back unchanged
+    fn assert_round_trip<T: AccountSerialize + AccountDeserialize>(account: &T, space: usize) {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= space, "{} bytes exceed the {} reserved", data.len(), space);
+        data.resize(space, 0);
+        let mut reserialized = Vec::new();
+        T::try_deserialize(&mut &data[..]).unwrap().try_serialize(&mut reserialized).unwrap();
+        assert_eq!(reserialized[..], data[..reserialized.len()]);
+    }
+
+    #[test]
+    fn leaderboard_round_trips_with_the_longest_name() {
+        let mut leaderboard = leaderboard();
+        leaderboard.exercise_name = "x".repeat(MAX_EXERCISE_NAME_LEN);
+        leaderboard.owner = Pubkey::new_unique();
+        leaderboard.total_submissions = u64::MAX;
+        leaderboard.record_holder = Pubkey::new_unique();
+        assert_round_trip(&leaderboard, LEADERBOARD_SPACE);
+    }
+
+    #[test]
+    fn user_score_round_trips_in_its_reserved_space() {
+        let mut user_score = user_score();
+        user_score.user = Pubkey::new_unique();
+        user_score.total_score = u64::MAX;
+        user_score.recent_nonces = [u64::MAX; RECENT_NONCES_LEN];
+        record_day(&mut user_score, 100, u64::MAX);
+        assert_round_trip(&user_score, USER_SCORE_SPACE);
+    }
+
+    #[test]
+    fn variable_length_accounts_fit_at_max_len() {
+        let space = 8 + ParticipantIndex::INIT_SPACE;
+        let mut page: ParticipantIndex = zeroed(space);
+        page.participants = vec![Pubkey::new_unique(); PARTICIPANT_PAGE_SIZE];
+        assert_round_trip(&page, space);
+
+        let space = 8 + Gym::INIT_SPACE;
+        let mut gym: Gym = zeroed(space);
+        gym.name = "g".repeat(MAX_GYM_NAME_LEN);
+        assert_round_trip(&gym, space);
+
+        let owners = vec![Pubkey::new_unique(); MAX_COUNCIL_OWNERS];
+        assert_round_trip(&council(&owners, 1), 8 + Council::INIT_SPACE);
+    }
+
+    #[test]
+    fn proposals_fit_their_largest_action() {
+        let members = vec![Pubkey::new_unique(); MAX_COUNCIL_OWNERS];
+        let account = ProposalAccount {
+            pubkey: Pubkey::new_unique(),
+            is_signer: true,
+            is_writable: true,
+        };
+        let mut proposal = proposal(&members);
+        for action in [
+            AdminAction::Invoke {
+                accounts: vec![account; MAX_PROPOSAL_ACCOUNTS],
+                data: vec![u8::MAX; MAX_PROPOSAL_DATA_LEN],
+            },
+            AdminAction::UpdateConfig(config()),
+            AdminAction::SetCouncil { owners: members.clone(), threshold: 1 },
+        ] {
+            proposal.action = action;
+            assert_round_trip(&proposal, 8 + Proposal::INIT_SPACE);
+        }
+    }
+
+    #[test]
+    fn fixed_size_accounts_round_trip() {
+        macro_rules! round_trip {
+            ($($account:ty),*) => {$(
+                let space = 8 + <$account>::INIT_SPACE;
+                assert_round_trip(&zeroed::<$account>(space), space);
+            )*};
+        }
+        round_trip!(
+            Treasury, CpiProgram, Moderator, Flag, ProofRecord, ScoreHistogram, GymStats,
+            RegionStats, NonceTracker, Waitlist
+        );
+    }
+}
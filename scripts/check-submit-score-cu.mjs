@@ -0,0 +1,125 @@
+#!/usr/bin/env node
+/**
+ * Compute-unit check for submit_score
+ *
+ * Simulates a returning user's submit_score on each leaderboard program and
+ * fails if it uses more compute units than SUBMIT_SCORE_CU_BUDGET. Run it
+ * against a deploy before and after a change to see the difference.
+ *
+ * The wallet must already have a UserScore on the board, so the simulation
+ * measures the hot path rather than first-time account creation. Optional
+ * accounts are left out, so boards that require a fee, gym or histogram
+ * account will report the error instead of a number.
+ */
+
+import {
+  Connection,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  TransactionInstruction,
+  TransactionMessage,
+  VersionedTransaction,
+} from '@solana/web3.js';
+import crypto from 'crypto';
+import fs from 'fs';
+import { join } from 'path';
+
+const PROGRAMS = {
+  pullups: new PublicKey('GDSkDgf6Q5mMN5kHZiKTXaAs2CLAkopDRDkSCM1tpcQa'),
+  jumps: new PublicKey('7ugCR1KLjHNgUjbW1pZGCadeCHKvUu7NwXsXDTTFypUd'),
+};
+
+const RPC_URL = process.env.SOLANA_DEVNET_RPC_URL || 'https://api.devnet.solana.com';
+// Defaults to the per-instruction limit; set it to a recorded baseline to catch creep
+const CU_BUDGET = Number(process.env.SUBMIT_SCORE_CU_BUDGET || 200_000);
+// SubmitScore has 14 optional accounts after the four required ones
+const OPTIONAL_ACCOUNTS = 14;
+
+function loadWallet() {
+  const walletPath = process.env.SOLANA_WALLET_PATH || join(process.env.HOME, '.config/solana/id.json');
+  return Keypair.fromSecretKey(new Uint8Array(JSON.parse(fs.readFileSync(walletPath, 'utf-8'))));
+}
+
+// Borsh-encoded submit_score arguments: a one-rep strict session with no
+// nonce, referrer or proof
+function submitScoreData(exercise) {
+  const discriminator = crypto.createHash('sha256').update('global:submit_score').digest().subarray(0, 8);
+  const args = Buffer.alloc(4 + 8 + 32 + 32);
+  args.writeUInt32LE(1, 0);
+  if (exercise !== 'pullups') {
+    return Buffer.concat([discriminator, args]);
+  }
+  // variant (Strict), added_weight_kg, sets = [1]
+  const pullupArgs = Buffer.alloc(1 + 2 + 4 + 2);
+  pullupArgs.writeUInt32LE(1, 3);
+  pullupArgs.writeUInt16LE(1, 7);
+  return Buffer.concat([discriminator, args, pullupArgs]);
+}
+
+async function measure(connection, wallet, exercise, programId) {
+  const [leaderboard] = PublicKey.findProgramAddressSync(
+    [Buffer.from('leaderboard'), Buffer.from(exercise)],
+    programId,
+  );
+  const [userScore] = PublicKey.findProgramAddressSync(
+    [Buffer.from('user_score'), leaderboard.toBuffer(), wallet.publicKey.toBuffer()],
+    programId,
+  );
+
+  // Anchor reads an omitted optional account as the program ID itself
+  const omitted = Array.from({ length: OPTIONAL_ACCOUNTS }, () => ({
+    pubkey: programId,
+    isSigner: false,
+    isWritable: false,
+  }));
+  const instruction = new TransactionInstruction({
+    programId,
+    keys: [
+      { pubkey: leaderboard, isSigner: false, isWritable: true },
+      { pubkey: userScore, isSigner: false, isWritable: true },
+      { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ...omitted,
+    ],
+    data: submitScoreData(exercise),
+  });
+
+  const { blockhash } = await connection.getLatestBlockhash();
+  const message = new TransactionMessage({
+    payerKey: wallet.publicKey,
+    recentBlockhash: blockhash,
+    instructions: [instruction],
+  }).compileToV0Message();
+  const { value } = await connection.simulateTransaction(new VersionedTransaction(message), {
+    sigVerify: false,
+    replaceRecentBlockhash: true,
+  });
+
+  if (value.err) {
+    console.error(`❌ ${exercise}: simulation failed`, value.err);
+    console.error((value.logs || []).join('\n'));
+    return false;
+  }
+  const withinBudget = value.unitsConsumed <= CU_BUDGET;
+  console.log(`${withinBudget ? '✅' : '❌'} ${exercise}: ${value.unitsConsumed} CU (budget ${CU_BUDGET})`);
+  return withinBudget;
+}
+
+async function main() {
+  console.log(`📡 RPC: ${RPC_URL}`);
+  const connection = new Connection(RPC_URL, 'confirmed');
+  const wallet = loadWallet();
+  console.log(`📝 Using wallet: ${wallet.publicKey.toString()}`);
+
+  let passed = true;
+  for (const [exercise, programId] of Object.entries(PROGRAMS)) {
+    passed = (await measure(connection, wallet, exercise, programId)) && passed;
+  }
+  process.exit(passed ? 0 : 1);
+}
+
+main().catch((error) => {
+  console.error(error);
+  process.exit(1);
+});